@@ -1,65 +1,181 @@
 mod utils;
 
-use std::rc::Rc;
+use std::{process::ExitCode, rc::Rc};
 
-use clap::{arg, command, Parser};
+use artistdb_core::{Artists, FormatOptions, SupportedSocials, Unavatar};
+use clap::{Parser, Subcommand};
 use inotify::{Inotify, WatchMask};
 use std::path::PathBuf;
 use tracing::error;
-use utils::{process_artists::Artists, supported_socials::SupportedSocials};
+use utils::{avatars::AvatarAssets, print_errors};
+
+/// `check` exit code when only warnings are found, clap exits with 2 on usage
+/// errors
+const WARNINGS_ONLY: u8 = 3;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
-    #[arg(short, long)]
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(short, long, global = true)]
     watch: bool,
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     format: bool,
-
-    #[arg(short, long, default_value = "./artists.txt")]
+    /// With --format, rewrite social code aliases to their canonical code
+    #[arg(long, global = true)]
+    canonical_codes: bool,
+    /// With --format, rewrite profile links of supported platforms to `username@code`
+    #[arg(long, global = true)]
+    match_links: bool,
+    /// With --format, rewrite usernames the way their platform normalizes them
+    #[arg(long, global = true)]
+    fix_usernames: bool,
+
+    #[arg(short, long, default_value = "./artists.txt", global = true)]
     in_file: String,
-    #[arg(short, long, default_value = "./src/public/artists")]
+    #[arg(short, long, default_value = "./src/public/artists", global = true)]
     out_dir: String,
-    #[arg(long, default_value = "500")]
+    /// Extra social platforms, added to or overriding the bundled ones
+    #[arg(long, default_value = "./socials.toml", global = true)]
+    socials: String,
+    /// Local `/path` avatars are read from here
+    #[arg(long, default_value = "./src/public/avatars", global = true)]
+    avatars_dir: String,
+    /// Downscaled local avatars are written here, served under the dir's name
+    #[arg(long, default_value = "./src/public/avatars-dist", global = true)]
+    avatars_out_dir: String,
+    /// `[[avatar]]` entries with the `url`, `colour` and `blurhash` of remote
+    /// avatars, painted until they load
+    #[arg(long, default_value = "./avatar-placeholders.toml", global = true)]
+    placeholder_cache: String,
+    /// Unavatar instance avatars are fetched from, for a self-hosted one
+    #[arg(long, default_value = "https://unavatar.io", global = true)]
+    unavatar_url: String,
+    #[arg(long, default_value = "500", global = true)]
     save_delay: u64,
 }
 
-fn main() {
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Validate the artists file without writing anything.
+    /// Exits with 1 if errors are found, 3 if only warnings are found
+    Check {
+        /// Treat warnings as errors
+        #[arg(long)]
+        deny_warnings: bool,
+    },
+}
+
+fn main() -> ExitCode {
     tracing_subscriber::fmt()
         .with_max_level(tracing::Level::DEBUG)
         .init();
 
     let args = Args::parse();
 
+    let supported_socials = match load_socials(&args.socials) {
+        Ok(supported_socials) => Rc::from(supported_socials),
+        Err(err) => {
+            error!("failed to read socials file: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
     if args.format {
-        let new_contents =
-            Artists::from_file(Rc::from(SupportedSocials::default()), &args.in_file).to_original();
-        let old_content = std::fs::read_to_string(&args.in_file).unwrap();
+        let old_content = match std::fs::read_to_string(&args.in_file) {
+            Ok(old_content) => old_content,
+            Err(err) => {
+                error!("failed to read artists file: {}", err);
+                return ExitCode::FAILURE;
+            }
+        };
+        let artists = Artists::parse(Rc::clone(&supported_socials), &old_content);
+        print_errors(
+            &old_content,
+            &args.in_file,
+            artists.errors(),
+            &supported_socials,
+        );
+        let options = FormatOptions {
+            canonical_codes: args.canonical_codes,
+            match_links: args.match_links,
+            fix_usernames: args.fix_usernames,
+        };
+        let new_contents = match artists.format_with(&options) {
+            Ok(new_contents) => new_contents,
+            Err(err) => {
+                eprintln!("{}", err);
+                return ExitCode::FAILURE;
+            }
+        };
 
         let bak_path = format!(
             "{}-{}.bak",
             &args.in_file,
             std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs()
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default()
         );
 
-        std::fs::write(bak_path, old_content).unwrap();
-        std::fs::write(&args.in_file, new_contents).unwrap();
+        if let Err(err) = std::fs::write(&bak_path, old_content) {
+            error!("failed to write backup file {}: {}", bak_path, err);
+            return ExitCode::FAILURE;
+        }
+        // In place, keeping the file's inode and mode so `-w` keeps watching it
+        if let Err(err) = std::fs::write(&args.in_file, new_contents) {
+            error!("failed to write artists file: {}", err);
+            return ExitCode::FAILURE;
+        }
 
-        return;
+        return ExitCode::SUCCESS;
     }
 
     let pipeline = crate::utils::pipeline::Pipeline {
         in_file: &args.in_file,
         out_dir: &args.out_dir,
-        supported_socials: Rc::from(SupportedSocials::default()),
+        supported_socials,
+        unavatar: Unavatar {
+            base_url: args.unavatar_url.trim_end_matches('/').to_string(),
+        },
+        avatars: AvatarAssets {
+            src_dir: &args.avatars_dir,
+            out_dir: &args.avatars_out_dir,
+        },
+        placeholder_cache: &args.placeholder_cache,
     };
-    pipeline.run();
+
+    if let Some(Command::Check { deny_warnings }) = args.command {
+        return match pipeline.check() {
+            Ok(report) => {
+                eprintln!("{}", report);
+                if report.errors > 0 || (report.warnings > 0 && deny_warnings) {
+                    ExitCode::from(1)
+                } else if report.warnings > 0 {
+                    ExitCode::from(WARNINGS_ONLY)
+                } else {
+                    ExitCode::SUCCESS
+                }
+            }
+            Err(err) => {
+                error!("{}", err);
+                ExitCode::from(1)
+            }
+        };
+    }
+
+    if let Err(err) = pipeline.run() {
+        error!("{}", err);
+        // Watching carries on, the next save may fix it
+        if !args.watch {
+            return ExitCode::FAILURE;
+        }
+    }
 
     if !args.watch {
-        return;
+        return ExitCode::SUCCESS;
     }
 
     let mut inotify = Inotify::init().expect("Error while initializing inotify instance");
@@ -87,6 +203,16 @@ fn main() {
             }
         }
 
-        pipeline.run();
+        if let Err(err) = pipeline.run() {
+            error!("{}", err);
+        }
+    }
+}
+
+/// The bundled registry, extended by the socials file if there's one
+fn load_socials(path: &str) -> std::io::Result<SupportedSocials> {
+    if !std::path::Path::new(path).exists() {
+        return Ok(SupportedSocials::default());
     }
+    SupportedSocials::from_path(path)
 }
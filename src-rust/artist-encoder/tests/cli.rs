@@ -0,0 +1,77 @@
+use std::process::{Command, Output};
+
+use tempfile::TempDir;
+
+/// Run the encoder in a fresh dir holding `artists.txt`, so every default path
+/// points inside it
+fn encoder(artists: &str, args: &[&str]) -> (TempDir, Output) {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("artists.txt"), artists).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_artist-encoder"))
+        .args(args)
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    (dir, output)
+}
+
+fn exit_code(artists: &str, args: &[&str]) -> i32 {
+    encoder(artists, args).1.status.code().unwrap()
+}
+
+const CLEAN: &str = "foo,Foo\nfoo@twitter\n";
+const WARNINGS: &str = "foo,Foo\nfoo@twitter\nfoo@twitter\n";
+const ERRORS: &str = "foo,Foo\nfoo@nope\n";
+
+#[test]
+fn check_exit_codes() {
+    assert_eq!(exit_code(CLEAN, &["check"]), 0);
+    assert_eq!(exit_code(WARNINGS, &["check"]), 3);
+    assert_eq!(exit_code(WARNINGS, &["check", "--deny-warnings"]), 1);
+    assert_eq!(exit_code(ERRORS, &["check"]), 1);
+    // Usage errors are clap's own 2
+    assert_eq!(exit_code(CLEAN, &["check", "--nope"]), 2);
+}
+
+#[test]
+fn check_takes_global_options() {
+    assert_eq!(exit_code(ERRORS, &["check", "--in-file", "missing.txt"]), 1);
+    let (dir, output) = encoder(CLEAN, &["check", "-i", "artists.txt"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(!dir.path().join("src").exists());
+}
+
+#[test]
+fn format_failures() {
+    assert_eq!(exit_code(CLEAN, &["--format", "-i", "missing.txt"]), 1);
+    assert_eq!(exit_code(CLEAN, &["--format"]), 0);
+}
+
+#[test]
+fn run_failures() {
+    let (dir, output) = encoder(CLEAN, &[]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(dir.path().join("src/public/artists/foo").exists());
+
+    assert_eq!(exit_code(WARNINGS, &[]), 0);
+    assert_eq!(exit_code(ERRORS, &[]), 1);
+
+    let (dir, output) = encoder("foo,Foo,/foo.png\nfoo@twitter\n", &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!dir.path().join("src/public/artists").exists());
+}
+
+#[test]
+fn run_restores_manual_edits() {
+    let (dir, _) = encoder(CLEAN, &[]);
+    let foo = dir.path().join("src/public/artists/foo");
+    let published = std::fs::read(&foo).unwrap();
+
+    std::fs::remove_file(&foo).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_artist-encoder"))
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(std::fs::read(&foo).unwrap(), published);
+}
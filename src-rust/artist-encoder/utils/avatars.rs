@@ -0,0 +1,376 @@
+//! Local avatars from the avatars dir, cropped square and downscaled to each
+//! of [`SIZES`], for the page's `srcset`. Each size is lossless WebP, or JPEG
+//! when that's smaller and the image is opaque: the `image` crate only encodes
+//! lossless WebP, and has no AVIF encoder without the rav1e toolchain.
+//!
+//! Files are named `<stem>-<hash>-<width>.<ext>`, after the md5 of the source
+//! and the encoding settings, so a changed image never hits a stale cache.
+//! The placeholder of every hash is kept in [`PLACEHOLDERS_FILE`] next to
+//! them, so an unchanged avatar is neither encoded nor decoded again.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Cursor,
+    path::Path,
+};
+
+use artistdb_core::{AvatarPlaceholder, AvatarProvider, AvatarSource};
+use image::{
+    codecs::{jpeg::JpegEncoder, webp::WebPEncoder},
+    imageops::FilterType,
+    DynamicImage, ImageReader,
+};
+use md5::{Digest, Md5};
+
+use crate::utils::output::{publish_dir, Changes};
+
+/// Square sizes local avatars are downscaled to: the page shows them 240px
+/// wide at most, and blurred full screen behind
+pub const SIZES: [u32; 2] = [200, 400];
+const JPEG_QUALITY: u8 = 85;
+/// Bump when the encoding changes, so every avatar is encoded again
+const ENCODING_VERSION: u8 = 2;
+const EXTENSIONS: [&str; 2] = ["webp", "jpg"];
+/// Avatar file names always end with the width, so this can't shadow one
+pub const PLACEHOLDERS_FILE: &str = "placeholders.json";
+
+pub struct AvatarAssets<'a> {
+    pub src_dir: &'a String,
+    pub out_dir: &'a String,
+}
+
+/// The `srcset` and the placeholder of every processed local avatar, by its
+/// `/path` on the info line
+#[derive(Debug, Default)]
+pub struct HashedAvatars {
+    urls: HashMap<String, (String, AvatarPlaceholder)>,
+}
+
+impl AvatarProvider for HashedAvatars {
+    fn url(&self, source: &AvatarSource) -> Option<String> {
+        match source {
+            AvatarSource::Local(path) => self.urls.get(path).map(|(url, _)| url.clone()),
+            _ => None,
+        }
+    }
+
+    fn placeholder(&self, source: &AvatarSource, _url: &str) -> Option<AvatarPlaceholder> {
+        match source {
+            AvatarSource::Local(path) => self
+                .urls
+                .get(path)
+                .map(|(_, placeholder)| placeholder.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// The average colour and the blurhash of the image
+fn placeholder(image: &DynamicImage) -> Result<AvatarPlaceholder, String> {
+    // Both only need a handful of pixels
+    let thumbnail = image.resize_exact(32, 32, FilterType::Triangle).to_rgba8();
+    let blurhash = blurhash::encode(4, 4, 32, 32, thumbnail.as_raw())
+        .map_err(|err| format!("can't compute blurhash: {}", err))?;
+
+    let pixels = thumbnail.pixels().count() as u64;
+    let mut sums = [0u64; 3];
+    thumbnail.pixels().for_each(|pixel| {
+        sums.iter_mut()
+            .zip(pixel.0)
+            .for_each(|(sum, channel)| *sum += channel as u64)
+    });
+    let colour = format!(
+        "#{:02x}{:02x}{:02x}",
+        sums[0] / pixels,
+        sums[1] / pixels,
+        sums[2] / pixels
+    );
+    Ok(AvatarPlaceholder { colour, blurhash })
+}
+
+impl<'a> AvatarAssets<'a> {
+    /// Served from the site root under the out dir's name
+    fn public_path(&self) -> String {
+        let name = Path::new(self.out_dir)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        format!("/{}", name)
+    }
+
+    /// Publish the out dir with every path, untouched if no avatar changed
+    pub fn build<'p>(
+        &self,
+        paths: impl Iterator<Item = &'p String>,
+    ) -> Result<(HashedAvatars, Changes), String> {
+        // A missing or unreadable cache only means decoding again
+        let cache = std::fs::read(Path::new(self.out_dir).join(PLACEHOLDERS_FILE))
+            .ok()
+            .and_then(|raw| {
+                serde_json::from_slice::<BTreeMap<String, AvatarPlaceholder>>(&raw).ok()
+            })
+            .unwrap_or_default();
+        let mut placeholders = BTreeMap::new();
+
+        let mut hashed = HashedAvatars::default();
+        let mut files = BTreeMap::new();
+        for path in paths {
+            if hashed.urls.contains_key(path) {
+                continue;
+            }
+            let processed = self.process(path, &mut files, &cache, &mut placeholders)?;
+            hashed.urls.insert(path.clone(), processed);
+        }
+        // Only the published avatars, so it doesn't grow forever
+        files.insert(
+            PLACEHOLDERS_FILE.to_string(),
+            serde_json::to_vec(&placeholders).unwrap_or_default(),
+        );
+        let changes = publish_dir(self.out_dir, &files)
+            .map_err(|err| format!("can't write avatars: {}", err))?;
+        Ok((hashed, changes))
+    }
+
+    /// Add every size of the avatar to `files`, by file name, reusing the
+    /// published ones if the source didn't change. Return the `srcset` and the
+    /// placeholder
+    fn process(
+        &self,
+        path: &str,
+        files: &mut BTreeMap<String, Vec<u8>>,
+        cache: &BTreeMap<String, AvatarPlaceholder>,
+        placeholders: &mut BTreeMap<String, AvatarPlaceholder>,
+    ) -> Result<(String, AvatarPlaceholder), String> {
+        let src = Path::new(self.src_dir).join(path.trim_start_matches('/'));
+        let raw =
+            std::fs::read(&src).map_err(|err| format!("{}: can't read avatar: {}", path, err))?;
+        // Safe in a URL and in a `srcset`
+        let stem = src
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default()
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+                _ => '-',
+            })
+            .collect::<String>();
+        let mut hasher = Md5::new();
+        hasher.update(&raw);
+        SIZES
+            .iter()
+            .for_each(|size| hasher.update(size.to_le_bytes()));
+        hasher.update([JPEG_QUALITY, ENCODING_VERSION]);
+        let hash = format!("{:x}", hasher.finalize());
+        let base = format!("{}-{}", stem, &hash[..10]);
+
+        // Only the header is read, small sources make fewer sizes
+        let (width, height) = ImageReader::new(Cursor::new(&raw))
+            .with_guessed_format()
+            .map_err(|err| err.to_string())
+            .and_then(|reader| reader.into_dimensions().map_err(|err| err.to_string()))
+            .map_err(|err| format!("{}: can't read avatar: {}", path, err))?;
+        let mut widths = SIZES.map(|size| size.min(width).min(height)).to_vec();
+        widths.dedup();
+
+        let published = widths
+            .iter()
+            .map(|width| {
+                EXTENSIONS.iter().find_map(|extension| {
+                    let name = format!("{}-{}.{}", base, width, extension);
+                    let encoded = std::fs::read(Path::new(self.out_dir).join(&name)).ok()?;
+                    Some((*width, name, encoded))
+                })
+            })
+            .collect::<Option<Vec<(u32, String, Vec<u8>)>>>();
+        let (encoded, placeholder) = match (published, cache.get(&base)) {
+            (Some(published), Some(cached)) => (published, cached.clone()),
+            _ => {
+                let image = image::load_from_memory(&raw)
+                    .map_err(|err| format!("{}: can't read avatar: {}", path, err))?;
+                let placeholder =
+                    placeholder(&image).map_err(|err| format!("{}: {}", path, err))?;
+                let mut encoded = vec![];
+                for width in &widths {
+                    let (extension, contents) = encode(&image, *width)
+                        .map_err(|err| format!("{}: can't encode avatar: {}", path, err))?;
+                    encoded.push((
+                        *width,
+                        format!("{}-{}.{}", base, width, extension),
+                        contents,
+                    ));
+                }
+                (encoded, placeholder)
+            }
+        };
+
+        let srcset = encoded
+            .iter()
+            .map(|(width, name, _)| format!("{}/{} {}w", self.public_path(), name, width))
+            .collect::<Vec<String>>()
+            .join(" ");
+        files.extend(
+            encoded
+                .into_iter()
+                .map(|(_, name, contents)| (name, contents)),
+        );
+        placeholders.insert(base, placeholder.clone());
+        Ok((srcset, placeholder))
+    }
+}
+
+/// Crop the image square and downscale it to `width`, return the extension
+/// and the contents of the smallest encoding
+fn encode(image: &DynamicImage, width: u32) -> Result<(&'static str, Vec<u8>), image::ImageError> {
+    let resized = image.resize_to_fill(width, width, FilterType::Lanczos3);
+
+    let rgba = resized.to_rgba8();
+    let mut webp = vec![];
+    rgba.write_with_encoder(WebPEncoder::new_lossless(Cursor::new(&mut webp)))?;
+    // JPEG has no alpha
+    if rgba.pixels().any(|pixel| pixel.0[3] < 255) {
+        return Ok(("webp", webp));
+    }
+
+    // Photos are much smaller lossy
+    let mut jpeg = vec![];
+    resized
+        .to_rgb8()
+        .write_with_encoder(JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY))?;
+    if jpeg.len() < webp.len() {
+        Ok(("jpg", jpeg))
+    } else {
+        Ok(("webp", webp))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgb, RgbImage, Rgba, RgbaImage};
+
+    use super::*;
+
+    fn build(src_dir: &Path, out_dir: &Path, path: &str) -> (String, AvatarPlaceholder, Changes) {
+        let src_dir = src_dir.to_string_lossy().to_string();
+        let out_dir = out_dir.to_string_lossy().to_string();
+        let assets = AvatarAssets {
+            src_dir: &src_dir,
+            out_dir: &out_dir,
+        };
+        let (hashed, changes) = assets.build([path.to_string()].iter()).unwrap();
+        let source = AvatarSource::Local(path.to_string());
+        let srcset = hashed.url(&source).unwrap();
+        let placeholder = hashed.placeholder(&source, &srcset).unwrap();
+        (srcset, placeholder, changes)
+    }
+
+    /// File name and width of every candidate
+    fn candidates(srcset: &str) -> Vec<(String, u32)> {
+        let parts = srcset.split(' ').collect::<Vec<&str>>();
+        parts
+            .chunks(2)
+            .map(|pair| {
+                let name = pair[0].strip_prefix("/avatars-dist/").unwrap();
+                let width = pair[1].strip_suffix('w').unwrap().parse().unwrap();
+                (name.to_string(), width)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn encode_avatars() {
+        let dir = tempfile::tempdir().unwrap();
+        let out_dir = dir.path().join("avatars-dist");
+
+        // Noise, like a photo, is smaller lossy
+        let photo = RgbImage::from_fn(800, 600, |x, y| {
+            Rgb([(x * 7 + y * 13) as u8, (x * y) as u8, (x ^ y) as u8])
+        });
+        photo.save(dir.path().join("fan art.png")).unwrap();
+        let (srcset, _, changes) = build(dir.path(), &out_dir, "/fan art.png");
+        let written = candidates(&srcset);
+        assert_eq!(
+            written
+                .iter()
+                .map(|(_, width)| *width)
+                .collect::<Vec<u32>>(),
+            SIZES
+        );
+        for (name, width) in &written {
+            assert!(name.starts_with("fan-art-") && name.ends_with(&format!("-{}.jpg", width)));
+            let image = image::open(out_dir.join(name)).unwrap();
+            assert_eq!((image.width(), image.height()), (*width, *width));
+        }
+        // Both sizes and the placeholders
+        assert_eq!(changes.added, 3);
+
+        // Small sources are only cropped, transparency stays
+        let icon = RgbaImage::from_pixel(100, 50, Rgba([255, 0, 0, 128]));
+        icon.save(dir.path().join("icon.png")).unwrap();
+        let (srcset, _, _) = build(dir.path(), &out_dir, "/icon.png");
+        let written = candidates(&srcset);
+        assert_eq!(written.len(), 1);
+        let (name, width) = &written[0];
+        assert!(name.ends_with("-50.webp"));
+        assert_eq!(*width, 50);
+        let image = image::open(out_dir.join(name)).unwrap();
+        assert_eq!(image.to_rgba8().get_pixel(0, 0), &Rgba([255, 0, 0, 128]));
+
+        let flat = RgbImage::from_pixel(300, 300, Rgb([51, 102, 153]));
+        flat.save(dir.path().join("flat.png")).unwrap();
+        let (_, placeholder, _) = build(dir.path(), &out_dir, "/flat.png");
+        assert_eq!(placeholder.colour, "#336699");
+        assert!(!placeholder.blurhash.is_empty());
+    }
+
+    #[test]
+    fn reuse_published() {
+        let dir = tempfile::tempdir().unwrap();
+        let out_dir = dir.path().join("avatars-dist");
+        let src = dir.path().join("foo.png");
+        RgbImage::from_pixel(64, 64, Rgb([255, 0, 0]))
+            .save(&src)
+            .unwrap();
+
+        let (srcset, placeholder, _) = build(dir.path(), &out_dir, "/foo.png");
+        assert_eq!(placeholder.colour, "#ff0000");
+        let (same, _, changes) = build(dir.path(), &out_dir, "/foo.png");
+        assert_eq!(same, srcset);
+        assert!(changes.is_empty());
+
+        // Neither encoded nor decoded again, whatever is published is kept
+        let (name, _) = candidates(&srcset).remove(0);
+        let published = out_dir.join(&name);
+        RgbImage::from_pixel(64, 64, Rgb([0, 0, 255]))
+            .save_with_format(&published, image::ImageFormat::WebP)
+            .unwrap();
+        let edited = std::fs::read(&published).unwrap();
+        let (same, placeholder, _) = build(dir.path(), &out_dir, "/foo.png");
+        assert_eq!(same, srcset);
+        assert_eq!(placeholder.colour, "#ff0000");
+        assert_eq!(std::fs::read(&published).unwrap(), edited);
+
+        // Encoded again without its placeholder
+        std::fs::remove_file(out_dir.join(PLACEHOLDERS_FILE)).unwrap();
+        let (same, placeholder, _) = build(dir.path(), &out_dir, "/foo.png");
+        assert_eq!(same, srcset);
+        assert_eq!(placeholder.colour, "#ff0000");
+        assert_ne!(std::fs::read(&published).unwrap(), edited);
+
+        // A changed source gets a new name
+        RgbImage::from_pixel(64, 64, Rgb([0, 255, 0]))
+            .save(&src)
+            .unwrap();
+        let (new, placeholder, changes) = build(dir.path(), &out_dir, "/foo.png");
+        assert_ne!(new, srcset);
+        assert_eq!(placeholder.colour, "#00ff00");
+        assert_eq!(
+            changes,
+            Changes {
+                added: 1,
+                updated: 1,
+                removed: 1,
+            }
+        );
+    }
+}
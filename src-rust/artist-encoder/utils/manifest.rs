@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+
+use murmur3::murmur3_x64_128;
+use serde::Serialize;
+
+/// Usernames and aliases never contain a `.`, so this can't shadow an artist
+pub const MANIFEST_FILE: &str = "manifest.json";
+const MANIFEST_VERSION: u32 = 1;
+
+/// The hashes of everything written to the output dir, so the site can tell
+/// stale cached files apart
+#[derive(Debug, Serialize)]
+pub struct Manifest {
+    pub version: u32,
+    /// Of the whole DB, from the hashes of every file
+    pub hash: String,
+    /// Hash of every file, by username, alias or the search index name
+    pub files: BTreeMap<String, String>,
+}
+
+/// murmur3 x64 128, as 32 hex digits
+fn hash(contents: &[u8]) -> String {
+    // Reading from a slice can't fail
+    let hash = murmur3_x64_128(&mut &contents[..], 0).unwrap_or_default();
+    format!("{:032x}", hash)
+}
+
+impl Manifest {
+    /// The manifest of the output files, by file name
+    pub fn new(files: &BTreeMap<String, Vec<u8>>) -> Manifest {
+        let files = files
+            .iter()
+            .map(|(name, contents)| (name.clone(), hash(contents)))
+            .collect::<BTreeMap<String, String>>();
+        let lines = files
+            .iter()
+            .map(|(name, hash)| format!("{}:{}\n", name, hash))
+            .collect::<String>();
+        Manifest {
+            version: MANIFEST_VERSION,
+            hash: hash(lines.as_bytes()),
+            files,
+        }
+    }
+
+    pub fn to_json(&self) -> Vec<u8> {
+        // Only strings, can't fail
+        serde_json::to_vec(self).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(entries: &[(&str, &str)]) -> BTreeMap<String, Vec<u8>> {
+        entries
+            .iter()
+            .map(|(name, contents)| (name.to_string(), contents.as_bytes().to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn stable_hashes() {
+        // Cached URLs depend on these, they must not change between builds
+        assert_eq!(hash(b""), "00000000000000000000000000000000");
+        assert_eq!(hash(b"foo"), "7eaf87e42bba7d87e271865701f54561");
+    }
+
+    #[test]
+    fn hash_every_file() {
+        let manifest = Manifest::new(&files(&[
+            ("foo", "artistdb 4\nfoo"),
+            ("fu", "@foo"),
+            ("index.txt", "artistdb-index 1\nfoo,Foo,fu"),
+        ]));
+        assert_eq!(manifest.version, MANIFEST_VERSION);
+        assert_eq!(
+            manifest.files.keys().collect::<Vec<&String>>(),
+            ["foo", "fu", "index.txt"]
+        );
+        // Aliases are hashed like the rest
+        assert_eq!(manifest.files["fu"], hash(b"@foo"));
+        assert_eq!(
+            manifest.hash,
+            hash(
+                format!(
+                    "foo:{}\nfu:{}\nindex.txt:{}\n",
+                    manifest.files["foo"], manifest.files["fu"], manifest.files["index.txt"]
+                )
+                .as_bytes()
+            )
+        );
+        assert_eq!(
+            String::from_utf8(manifest.to_json()).unwrap(),
+            format!(
+                r#"{{"version":1,"hash":"{}","files":{{"foo":"{}","fu":"{}","index.txt":"{}"}}}}"#,
+                manifest.hash,
+                manifest.files["foo"],
+                manifest.files["fu"],
+                manifest.files["index.txt"]
+            )
+        );
+    }
+
+    #[test]
+    fn hash_the_whole_db() {
+        let base = Manifest::new(&files(&[("foo", "Foo"), ("fu", "@foo")]));
+        let same = Manifest::new(&files(&[("fu", "@foo"), ("foo", "Foo")]));
+        assert_eq!(base.hash, same.hash);
+
+        let changed = Manifest::new(&files(&[("foo", "Foo!"), ("fu", "@foo")]));
+        let renamed = Manifest::new(&files(&[("foo", "Foo"), ("fuu", "@foo")]));
+        let removed = Manifest::new(&files(&[("foo", "Foo")]));
+        assert_ne!(changed.hash, base.hash);
+        assert_ne!(renamed.hash, base.hash);
+        assert_ne!(removed.hash, base.hash);
+    }
+}
@@ -0,0 +1,294 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::File,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use tracing::warn;
+
+/// What publishing a dir changed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Changes {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.added == 0 && self.updated == 0 && self.removed == 0
+    }
+}
+
+impl fmt::Display for Changes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} added, {} updated, {} removed",
+            self.added, self.updated, self.removed
+        )
+    }
+}
+
+/// Names of the published files to remove, one per line. Output file names
+/// never start with a `.`
+const REMOVED_FILE: &str = ".removed";
+
+/// Make `dir` hold exactly `files`, by file name, touching only the files that
+/// changed so a dev server watching it reloads only those. They are written
+/// and synced to a hidden sibling staging dir, which is renamed to
+/// `.<name>.ready` to commit the publish, then each is renamed into `dir` on
+/// its own, so readers never see a partial file. The dir isn't swapped as a
+/// whole: mid-publish, a reader can see some files new and others still old.
+/// A run that dies before the commit leaves `dir` untouched, one that dies
+/// after is finished by the next
+pub fn publish_dir(dir: &str, files: &BTreeMap<String, Vec<u8>>) -> Result<Changes, String> {
+    let dir = Path::new(dir);
+    let staging = sibling(dir, "staging")?;
+    let ready = sibling(dir, "ready")?;
+    recover(dir, &ready)?;
+    // Left behind by a run that died before committing
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging).map_err(|err| io_error("remove", &staging, err))?;
+    }
+
+    // Compare with what's published
+    let mut changes = Changes::default();
+    let mut changed = vec![];
+    for (name, contents) in files {
+        match std::fs::read(dir.join(name)) {
+            Ok(old) if old == *contents => continue,
+            Ok(_) => changes.updated += 1,
+            Err(_) => changes.added += 1,
+        }
+        changed.push((name, contents));
+    }
+    let removed = published_files(dir)
+        .map_err(|err| io_error("read", dir, err))?
+        .into_iter()
+        .filter(|name| !files.contains_key(name))
+        .collect::<Vec<String>>();
+    changes.removed = removed.len();
+    if dir.exists() && changes.is_empty() {
+        return Ok(changes);
+    }
+
+    std::fs::create_dir_all(&staging).map_err(|err| io_error("create", &staging, err))?;
+    for (name, contents) in changed {
+        let path = staging.join(name);
+        write_synced(&path, contents).map_err(|err| io_error("write", &path, err))?;
+    }
+    if !removed.is_empty() {
+        let path = staging.join(REMOVED_FILE);
+        write_synced(&path, removed.join("\n").as_bytes())
+            .map_err(|err| io_error("write", &path, err))?;
+    }
+    sync_dir(&staging).map_err(|err| io_error("sync", &staging, err))?;
+    std::fs::rename(&staging, &ready).map_err(|err| io_error("commit", &ready, err))?;
+    sync_dir(parent(dir)).map_err(|err| io_error("sync", parent(dir), err))?;
+
+    apply(dir, &ready).map_err(|err| io_error("publish", dir, err))?;
+    Ok(changes)
+}
+
+/// `<path>: can't <action>: <err>`
+fn io_error(action: &str, path: &Path, err: io::Error) -> String {
+    format!("{}: can't {}: {}", path.display(), action, err)
+}
+
+/// `.<name>.<suffix>` next to `dir`, on the same filesystem so its files can
+/// be renamed into it
+fn sibling(dir: &Path, suffix: &str) -> Result<PathBuf, String> {
+    let name = dir
+        .file_name()
+        .ok_or_else(|| format!("{}: not a dir name", dir.display()))?;
+    Ok(dir.with_file_name(format!(".{}.{}", name.to_string_lossy(), suffix)))
+}
+
+/// Names of the regular files in `dir`, none if it doesn't exist yet
+fn published_files(dir: &Path) -> io::Result<Vec<String>> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut names = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            names.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+    Ok(names)
+}
+
+/// Finish what a run that died midway left behind
+fn recover(dir: &Path, ready: &Path) -> Result<(), String> {
+    if ready.exists() {
+        warn!(
+            "{}: finishing the publish of a run that died",
+            dir.display()
+        );
+        apply(dir, ready).map_err(|err| io_error("publish", dir, err))?;
+    }
+    Ok(())
+}
+
+/// Move the files of the committed `ready` dir into `dir`, then remove the
+/// ones it lists as removed. Every step can be done again, so a run that dies
+/// midway is finished by the next
+fn apply(dir: &Path, ready: &Path) -> io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let staged = std::fs::read_dir(ready)?
+        .map(|entry| entry.map(|entry| entry.file_name()))
+        .collect::<io::Result<Vec<_>>>()?;
+    for name in staged.iter().filter(|name| *name != REMOVED_FILE) {
+        std::fs::rename(ready.join(name), dir.join(name))?;
+    }
+    match std::fs::read_to_string(ready.join(REMOVED_FILE)) {
+        Ok(removed) => {
+            for name in removed.lines() {
+                match std::fs::remove_file(dir.join(name)) {
+                    Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                    _ => {}
+                }
+            }
+        }
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+        Err(_) => {}
+    }
+    sync_dir(dir)?;
+    sync_dir(ready)?;
+    std::fs::remove_dir_all(ready)?;
+    sync_dir(parent(dir))
+}
+
+/// Write `contents` to `path` and flush them to disk
+fn write_synced(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+/// Flush the entries of `dir`, so the files created, renamed or removed in it
+/// survive a crash
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+/// The dir holding `dir`, `.` for a bare name
+fn parent(dir: &Path) -> &Path {
+    match dir.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::MetadataExt;
+
+    use super::*;
+
+    fn files(entries: &[(&str, &str)]) -> BTreeMap<String, Vec<u8>> {
+        entries
+            .iter()
+            .map(|(name, contents)| (name.to_string(), contents.as_bytes().to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn count_changes() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("artists");
+        let dir = dir.to_str().unwrap();
+
+        publish_dir(
+            dir,
+            &files(&[("foo", "Foo"), ("bar", "Bar"), ("baz", "Baz")]),
+        )
+        .unwrap();
+        let changes = publish_dir(
+            dir,
+            &files(&[
+                ("foo", "Foo"),
+                ("bar", "Bar!"),
+                ("qux", "Qux"),
+                ("quux", "Quux"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(
+            changes,
+            Changes {
+                added: 2,
+                updated: 1,
+                removed: 1,
+            }
+        );
+        assert_eq!(changes.to_string(), "2 added, 1 updated, 1 removed");
+        assert_eq!(
+            std::fs::read_to_string(root.path().join("artists/bar")).unwrap(),
+            "Bar!"
+        );
+        assert!(!root.path().join("artists/baz").exists());
+    }
+
+    #[test]
+    fn publish_in_place() {
+        let root = tempfile::tempdir().unwrap();
+        let path = root.path().join("artists");
+        let dir = path.to_str().unwrap();
+
+        let changes = publish_dir(dir, &files(&[("foo", "Foo"), ("bar", "Bar")])).unwrap();
+        assert_eq!(changes.added, 2);
+        assert_eq!(std::fs::read_to_string(path.join("foo")).unwrap(), "Foo");
+        // Nothing left next to it
+        assert_eq!(std::fs::read_dir(root.path()).unwrap().count(), 1);
+
+        let inode = |path: &Path| std::fs::metadata(path).unwrap().ino();
+        let (dir_inode, foo_inode) = (inode(&path), inode(&path.join("foo")));
+        let changes = publish_dir(dir, &files(&[("foo", "Foo"), ("bar", "Bar")])).unwrap();
+        assert!(changes.is_empty());
+        assert_eq!(inode(&path.join("foo")), foo_inode);
+
+        // Only the changed files are replaced, never the dir
+        publish_dir(dir, &files(&[("foo", "Foo"), ("bar", "Bar!")])).unwrap();
+        assert_eq!(inode(&path), dir_inode);
+        assert_eq!(inode(&path.join("foo")), foo_inode);
+        assert_eq!(std::fs::read_dir(root.path()).unwrap().count(), 1);
+
+        // An empty dir is published too
+        let empty = root.path().join("empty");
+        publish_dir(empty.to_str().unwrap(), &BTreeMap::new()).unwrap();
+        assert!(empty.is_dir());
+    }
+
+    #[test]
+    fn recover_dead_runs() {
+        let root = tempfile::tempdir().unwrap();
+        let path = root.path().join("artists");
+        let dir = path.to_str().unwrap();
+        publish_dir(dir, &files(&[("foo", "Foo"), ("bar", "Bar")])).unwrap();
+
+        // Died while staging: thrown away
+        let staging = root.path().join(".artists.staging");
+        std::fs::create_dir(&staging).unwrap();
+        std::fs::write(staging.join("foo"), "Fo").unwrap();
+        let changes = publish_dir(dir, &files(&[("foo", "Foo"), ("bar", "Bar")])).unwrap();
+        assert!(changes.is_empty());
+        assert_eq!(std::fs::read_to_string(path.join("foo")).unwrap(), "Foo");
+
+        // Died after committing, halfway through moving the files: finished
+        let ready = root.path().join(".artists.ready");
+        std::fs::create_dir(&ready).unwrap();
+        std::fs::write(ready.join("baz"), "Baz").unwrap();
+        std::fs::write(ready.join(REMOVED_FILE), "bar\nqux").unwrap();
+        std::fs::write(path.join("foo"), "Foo!").unwrap();
+        let changes = publish_dir(dir, &files(&[("foo", "Foo!"), ("baz", "Baz")])).unwrap();
+        assert!(changes.is_empty());
+        assert!(!ready.exists() && !staging.exists());
+        assert!(!path.join("bar").exists());
+        assert_eq!(std::fs::read_to_string(path.join("baz")).unwrap(), "Baz");
+    }
+}
@@ -1,58 +1,146 @@
-use std::{path::PathBuf, rc::Rc};
+use std::{collections::BTreeMap, fmt, path::Path, rc::Rc};
+
+use artistdb_core::{
+    Artist, Artists, AvatarProviders, AvatarSource, Error, ErrorKind, PlaceholderCache,
+    SearchIndex, Severity, SupportedSocials, Unavatar,
+};
+use tracing::info;
 
 use crate::utils::{
-    process_artists::{Artist, Artists},
-    supported_socials::SupportedSocials,
+    avatars::AvatarAssets,
+    manifest::{Manifest, MANIFEST_FILE},
+    output::{publish_dir, Changes},
+    print_errors,
 };
-use tracing::error;
 
 pub struct Pipeline<'a> {
     pub in_file: &'a String,
     pub out_dir: &'a String,
     pub supported_socials: Rc<SupportedSocials>,
+    pub unavatar: Unavatar,
+    pub avatars: AvatarAssets<'a>,
+    /// Placeholders of remote avatars, read on every run if the file exists
+    pub placeholder_cache: &'a String,
 }
 
-impl<'a> Pipeline<'a> {
-    pub fn run(&self) {
-        let artists = Artists::from_file(self.supported_socials.clone(), "artists.txt");
+/// Next to the artist files, a name no username or alias can have
+const INDEX_FILE: &str = "index.txt";
 
-        // re-create output dir, write files
-        self.recreate_out_dir()
-            .unwrap_or_else(|err| error!("{}", err));
+/// How many problems a pipeline run found, and what it wrote
+#[derive(Debug, Default)]
+pub struct Report {
+    pub errors: usize,
+    pub warnings: usize,
+    pub artists: Changes,
+    pub avatars: Changes,
+}
 
-        artists.get_artists().iter().for_each(|artist| {
-            self.write_to_out_dir(artist)
-                .unwrap_or_else(|err| error!("{}", err));
-        });
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} error(s), {} warning(s)", self.errors, self.warnings)
     }
+}
 
-    fn recreate_out_dir(&self) -> Result<(), String> {
-        if std::path::Path::new(&self.out_dir).exists() {
-            std::fs::remove_dir_all(self.out_dir)
-                .map_err(|err| format!("can't remove output dir: {}", err))?
+impl<'a> Pipeline<'a> {
+    /// Write the output dir, then fail if any error was found, so a broken
+    /// DB never passes CI
+    pub fn run(&self) -> Result<Report, String> {
+        let report = self.build(true)?;
+        if report.errors > 0 {
+            return Err(format!("{}: {}", self.in_file, report));
         }
-        std::fs::create_dir_all(self.out_dir)
-            .map_err(|err| format!("can't create output dir: {}", err))?;
+        Ok(report)
+    }
 
-        Ok(())
+    /// Same as run(), without touching the output dir
+    pub fn check(&self) -> Result<Report, String> {
+        self.build(false)
     }
 
-    fn write_to_out_dir(&self, artist: &Artist) -> Result<(), String> {
-        // Main files
-        let path = PathBuf::from(format!("{}/{}", self.out_dir, artist.username));
-        let contents = artist.serialize()?;
+    /// Parse, lint and serialize, print every problem found along the way, and
+    /// write the output dir if asked to
+    fn build(&self, write: bool) -> Result<Report, String> {
+        let source = std::fs::read_to_string(self.in_file)
+            .map_err(|err| format!("failed to read artists file: {}", err))?;
+        let mut artists = Artists::parse(self.supported_socials.clone(), &source);
+        artists.check_avatar_files(Path::new(self.avatars.src_dir));
+        let missing_avatars = artists
+            .errors()
+            .any(|err| matches!(err.kind, ErrorKind::MissingAvatarFile(_)));
 
-        std::fs::write(path, contents)
-            .map_err(|err| format!("{}: can't write dist file: {}", artist.username, err))?;
+        // Local avatars are only processed when they're about to be written
+        let mut providers = AvatarProviders::new(self.unavatar.clone());
+        if Path::new(self.placeholder_cache).exists() {
+            let cache = PlaceholderCache::from_path(self.placeholder_cache)
+                .map_err(|err| format!("failed to read placeholder cache: {}", err))?;
+            providers = providers.with_placeholders(cache);
+        }
+        let mut avatar_changes = Changes::default();
+        if write && !missing_avatars {
+            let paths = artists
+                .iter()
+                .flat_map(|artist| artist.avatar_sources())
+                .filter_map(|source| match source {
+                    AvatarSource::Local(path) => Some(path),
+                    _ => None,
+                })
+                .collect::<Vec<String>>();
+            let (hashed, changes) = self.avatars.build(paths.iter())?;
+            providers = providers.with_provider(hashed);
+            avatar_changes = changes;
+        }
 
-        // Alias files, contents: "@" + username
-        artist.alias.iter().for_each(|alias| {
-            let content = format!("@{}", artist.username);
-            let path = PathBuf::from(format!("{}/{}", self.out_dir, alias));
-            std::fs::write(path, content)
-                .unwrap_or_else(|err| error!("{}: can't write alias file: {}", alias, err));
+        let mut serialized: Vec<(&Artist, String)> = vec![];
+        let mut serialize_errors: Vec<Error> = vec![];
+        artists
+            .iter()
+            .for_each(|artist| match artist.serialize_with(&providers) {
+                Ok(contents) => serialized.push((artist, contents)),
+                Err(err) => serialize_errors.push(err),
+            });
+
+        let errors = artists
+            .errors()
+            .chain(serialize_errors.iter())
+            .collect::<Vec<&Error>>();
+        let mut report = Report::default();
+        errors.iter().for_each(|err| match err.severity() {
+            Severity::Error => report.errors += 1,
+            Severity::Warning => report.warnings += 1,
         });
+        print_errors(&source, self.in_file, errors, &self.supported_socials);
+
+        if !write {
+            return Ok(report);
+        }
+        if missing_avatars {
+            return Err("missing avatar files, nothing written".to_string());
+        }
+
+        // Compared with the files on disk, so manual edits are undone too
+        let mut files = output_files(serialized);
+        let manifest = Manifest::new(&files);
+        files.insert(MANIFEST_FILE.to_string(), manifest.to_json());
+        report.artists = publish_dir(self.out_dir, &files)?;
+        info!("{}: {}", self.out_dir, report.artists);
+        report.avatars = avatar_changes;
+        info!("{}: {}", self.avatars.out_dir, report.avatars);
 
-        Ok(())
+        Ok(report)
+    }
+}
+
+/// The contents of every file of the output dir, by file name: one per artist,
+/// one per alias containing `@username`, and the search index
+fn output_files(serialized: Vec<(&Artist, String)>) -> BTreeMap<String, Vec<u8>> {
+    let mut files = BTreeMap::new();
+    let index = SearchIndex::new(serialized.iter().map(|(artist, _)| *artist));
+    files.insert(INDEX_FILE.to_string(), index.encode().into_bytes());
+    for (artist, contents) in serialized {
+        files.insert(artist.username.clone(), contents.into_bytes());
+        artist.alias.iter().for_each(|alias| {
+            files.insert(alias.clone(), format!("@{}", artist.username).into_bytes());
+        });
     }
+    files
 }
@@ -0,0 +1,352 @@
+//! Turning where an avatar comes from into the URL the site loads, so the
+//! frontend never builds avatar URLs itself.
+
+use std::{collections::HashMap, io, path::Path};
+
+use md5::{Digest, Md5};
+use percent_encoding::utf8_percent_encode;
+use serde::Deserialize;
+
+use crate::{frontend::AvatarPlaceholder, process_url::SEGMENT};
+
+/// Where an artist's avatar comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AvatarSource {
+    /// `username@code`, the profile picture on a platform or an avatar provider
+    Social { code: String, username: String },
+    /// `//link` to an image
+    Url(String),
+    /// `/path` of an image under the avatars dir
+    Local(String),
+    /// `email@gravatar`
+    Email(String),
+}
+
+pub trait AvatarProvider {
+    /// The URL of the avatar, None if the provider can't serve this source
+    fn url(&self, source: &AvatarSource) -> Option<String>;
+
+    /// What to paint until the avatar at `url` loads, if the provider knows
+    /// the image
+    fn placeholder(&self, _source: &AvatarSource, _url: &str) -> Option<AvatarPlaceholder> {
+        None
+    }
+}
+
+/// Profile pictures through unavatar, or a self-hosted instance of it
+#[derive(Debug, Clone)]
+pub struct Unavatar {
+    /// Without the trailing `/`
+    pub base_url: String,
+}
+
+impl Default for Unavatar {
+    fn default() -> Self {
+        Unavatar {
+            base_url: "https://unavatar.io".to_string(),
+        }
+    }
+}
+
+impl AvatarProvider for Unavatar {
+    fn url(&self, source: &AvatarSource) -> Option<String> {
+        let AvatarSource::Social { code, username } = source else {
+            return None;
+        };
+        // Failing loudly lets the frontend switch to the fallback
+        Some(format!(
+            "{}/{}/{}?size=400&fallback=false",
+            self.base_url,
+            code,
+            utf8_percent_encode(username, SEGMENT)
+        ))
+    }
+}
+
+/// `//link` avatars, as is
+#[derive(Debug, Clone, Default)]
+pub struct Direct;
+
+impl AvatarProvider for Direct {
+    fn url(&self, source: &AvatarSource) -> Option<String> {
+        match source {
+            AvatarSource::Url(url) => Some(url.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// `/path` avatars, served from the site's own avatars dir
+#[derive(Debug, Clone)]
+pub struct Local {
+    /// The public path of the dir, without the trailing `/`
+    pub dir: String,
+}
+
+impl Default for Local {
+    fn default() -> Self {
+        Local {
+            dir: "/avatars".to_string(),
+        }
+    }
+}
+
+impl AvatarProvider for Local {
+    fn url(&self, source: &AvatarSource) -> Option<String> {
+        match source {
+            AvatarSource::Local(path) => Some(format!(
+                "{}{}",
+                self.dir,
+                path.split('/')
+                    .map(|segment| utf8_percent_encode(segment, SEGMENT).to_string())
+                    .collect::<Vec<String>>()
+                    .join("/")
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Gravatar, the email is hashed here so it never ends up on the site
+#[derive(Debug, Clone, Default)]
+pub struct Gravatar;
+
+impl AvatarProvider for Gravatar {
+    fn url(&self, source: &AvatarSource) -> Option<String> {
+        let AvatarSource::Email(email) = source else {
+            return None;
+        };
+        let hash = Md5::digest(email.trim().to_lowercase().as_bytes());
+        Some(format!(
+            "https://gravatar.com/avatar/{:x}?s=400&d=404",
+            hash
+        ))
+    }
+}
+
+/// The providers tried in order, and the avatar shown when none of them can
+/// serve the source or the image fails to load
+pub struct AvatarProviders {
+    providers: Vec<Box<dyn AvatarProvider>>,
+    /// Placeholders of the remote avatars, for the ones no provider knows
+    placeholders: PlaceholderCache,
+    pub fallback: String,
+}
+
+impl Default for AvatarProviders {
+    fn default() -> Self {
+        AvatarProviders::new(Unavatar::default())
+    }
+}
+
+impl AvatarProviders {
+    /// The bundled providers, with `unavatar` for social avatars
+    pub fn new(unavatar: Unavatar) -> Self {
+        AvatarProviders {
+            providers: vec![
+                Box::new(Direct),
+                Box::new(Local::default()),
+                Box::new(Gravatar),
+                Box::new(unavatar),
+            ],
+            placeholders: PlaceholderCache::default(),
+            fallback: "/avatar.svg".to_string(),
+        }
+    }
+
+    /// Try `provider` before the others
+    pub fn with_provider(mut self, provider: impl AvatarProvider + 'static) -> Self {
+        self.providers.insert(0, Box::new(provider));
+        self
+    }
+
+    pub fn with_placeholders(mut self, placeholders: PlaceholderCache) -> Self {
+        self.placeholders = placeholders;
+        self
+    }
+
+    pub fn with_fallback(mut self, fallback: &str) -> Self {
+        self.fallback = fallback.to_string();
+        self
+    }
+
+    /// The URL of the first provider that can serve the source
+    pub fn url(&self, source: &AvatarSource) -> Option<String> {
+        self.providers
+            .iter()
+            .find_map(|provider| provider.url(source))
+    }
+
+    /// The placeholder of the first provider that knows the source's image,
+    /// else the cached one of its URL
+    pub fn placeholder(&self, source: &AvatarSource) -> Option<AvatarPlaceholder> {
+        let url = self.url(source)?;
+        self.providers
+            .iter()
+            .find_map(|provider| provider.placeholder(source, &url))
+            .or_else(|| self.placeholders.get(&url))
+    }
+}
+
+/// One `[[avatar]]` entry of a placeholder cache file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CachedPlaceholder {
+    url: String,
+    colour: String,
+    #[serde(default)]
+    blurhash: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PlaceholderCacheFile {
+    #[serde(default)]
+    avatar: Vec<CachedPlaceholder>,
+}
+
+/// Placeholders of remote avatars, computed ahead of time and looked up by
+/// avatar URL
+#[derive(Debug, Default)]
+pub struct PlaceholderCache {
+    placeholders: HashMap<String, AvatarPlaceholder>,
+}
+
+impl PlaceholderCache {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<PlaceholderCache> {
+        let raw = std::fs::read_to_string(&path)?;
+        let mut new = PlaceholderCache::default();
+        new.extend_from_str(&raw)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        Ok(new)
+    }
+
+    /// Add the `[[avatar]]` entries of a cache file, later ones win
+    pub fn extend_from_str(&mut self, raw: &str) -> Result<(), toml::de::Error> {
+        let file: PlaceholderCacheFile = toml::from_str(raw)?;
+        self.placeholders
+            .extend(file.avatar.into_iter().map(|cached| {
+                (
+                    cached.url,
+                    AvatarPlaceholder {
+                        colour: cached.colour,
+                        blurhash: cached.blurhash,
+                    },
+                )
+            }));
+        Ok(())
+    }
+
+    pub fn get(&self, url: &str) -> Option<AvatarPlaceholder> {
+        self.placeholders.get(url).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn social(code: &str, username: &str) -> AvatarSource {
+        AvatarSource::Social {
+            code: code.to_string(),
+            username: username.to_string(),
+        }
+    }
+
+    #[test]
+    fn resolve_urls() {
+        let providers = AvatarProviders::default();
+
+        assert_eq!(
+            providers.url(&social("twitter", "foo")).unwrap(),
+            "https://unavatar.io/twitter/foo?size=400&fallback=false"
+        );
+        assert_eq!(
+            providers.url(&social("reddit", "foo/../bar?#")).unwrap(),
+            "https://unavatar.io/reddit/foo%2F..%2Fbar%3F%23?size=400&fallback=false"
+        );
+        assert_eq!(
+            providers
+                .url(&AvatarSource::Url("//example.com/a.png".to_string()))
+                .unwrap(),
+            "//example.com/a.png"
+        );
+        assert_eq!(
+            providers
+                .url(&AvatarSource::Local("/foo.png".to_string()))
+                .unwrap(),
+            "/avatars/foo.png"
+        );
+        assert_eq!(
+            providers
+                .url(&AvatarSource::Local("/fan art/#1?.png".to_string()))
+                .unwrap(),
+            "/avatars/fan%20art/%231%3F.png"
+        );
+        assert_eq!(
+            providers
+                .url(&AvatarSource::Email(
+                    " MyEmailAddress@example.com ".to_string()
+                ))
+                .unwrap(),
+            "https://gravatar.com/avatar/0bc83cb571cd1c50ba6f3e8a78ef1346?s=400&d=404"
+        );
+    }
+
+    #[test]
+    fn cached_placeholders() {
+        let mut cache = PlaceholderCache::default();
+        cache
+            .extend_from_str(
+                r##"
+                [[avatar]]
+                url = "https://unavatar.io/twitter/foo?size=400&fallback=false"
+                colour = "#336699"
+                blurhash = "LEHV6nWB2yk8pyo0adR*.7kCMdnj"
+                "##,
+            )
+            .unwrap();
+        let providers = AvatarProviders::default().with_placeholders(cache);
+
+        assert_eq!(
+            providers.placeholder(&social("twitter", "foo")),
+            Some(AvatarPlaceholder {
+                colour: "#336699".to_string(),
+                blurhash: "LEHV6nWB2yk8pyo0adR*.7kCMdnj".to_string(),
+            })
+        );
+        assert_eq!(providers.placeholder(&social("github", "foo")), None);
+        assert_eq!(
+            providers.url(&social("twitter", "foo")).unwrap(),
+            "https://unavatar.io/twitter/foo?size=400&fallback=false"
+        );
+    }
+
+    #[test]
+    fn custom_providers() {
+        struct Mirror;
+        impl AvatarProvider for Mirror {
+            fn url(&self, source: &AvatarSource) -> Option<String> {
+                match source {
+                    AvatarSource::Social { code, username } if code == "github" => {
+                        Some(format!("https://github.com/{}.png", username))
+                    }
+                    _ => None,
+                }
+            }
+        }
+
+        let providers = AvatarProviders::new(Unavatar {
+            base_url: "https://unavatar.example.com".to_string(),
+        })
+        .with_provider(Mirror);
+        assert_eq!(
+            providers.url(&social("github", "foo")).unwrap(),
+            "https://github.com/foo.png"
+        );
+        assert_eq!(
+            providers.url(&social("twitter", "foo")).unwrap(),
+            "https://unavatar.example.com/twitter/foo?size=400&fallback=false"
+        );
+    }
+}
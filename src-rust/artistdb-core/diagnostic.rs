@@ -0,0 +1,216 @@
+use crate::{
+    error::{Error, ErrorKind},
+    lines_with_offsets,
+    supported_socials::SupportedSocials,
+    BOM,
+};
+
+/// Renders errors rustc-style: the artist block the error belongs to, a caret
+/// under the offending component and a help note
+pub struct Renderer<'a> {
+    source: &'a str,
+    path: &'a str,
+    supported_socials: &'a SupportedSocials,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(
+        source: &'a str,
+        path: &'a str,
+        supported_socials: &'a SupportedSocials,
+    ) -> Renderer<'a> {
+        Renderer {
+            source,
+            path,
+            supported_socials,
+        }
+    }
+
+    pub fn render(&self, err: &Error) -> String {
+        let lines = lines_with_offsets(self.source).collect::<Vec<(usize, &str)>>();
+        let mut out = format!("{}: {}\n", err.severity(), err.kind);
+
+        let Some(&(mut line_start, mut line)) = lines.get(err.line.wrapping_sub(1)) else {
+            out.push_str(&format!("  --> {}\n", self.path));
+            self.push_notes(&mut out, err, 2);
+            return out;
+        };
+        if let Some(rest) = line.strip_prefix(BOM) {
+            line_start += BOM.len_utf8();
+            line = rest;
+        }
+
+        // The block is every non-blank line around the offending one
+        let index = err.line - 1;
+        let is_blank = |i: usize| lines[i].1.trim().is_empty();
+        let mut first = index;
+        while first > 0 && !is_blank(first - 1) {
+            first -= 1;
+        }
+        let mut last = index;
+        while last + 1 < lines.len() && !is_blank(last + 1) {
+            last += 1;
+        }
+
+        let gutter = (last + 1).to_string().len();
+        let start = err.span.start.clamp(line_start, line_start + line.len()) - line_start;
+        let end = err
+            .span
+            .end
+            .clamp(line_start + start, line_start + line.len())
+            - line_start;
+        let column = line[..start].chars().count();
+        let width = line[start..end].chars().count().max(1);
+
+        out.push_str(&format!(
+            "{:gutter$}--> {}:{}:{}\n",
+            "",
+            self.path,
+            err.line,
+            column + 1
+        ));
+        out.push_str(&format!("{:gutter$} |\n", ""));
+        for (i, (_, text)) in lines.iter().enumerate().take(last + 1).skip(first) {
+            let text = text.strip_prefix(BOM).unwrap_or(text);
+            out.push_str(&format!("{:>gutter$} | {}\n", i + 1, text));
+            if i == index {
+                out.push_str(&format!(
+                    "{:gutter$} | {}{}\n",
+                    "",
+                    " ".repeat(column),
+                    "^".repeat(width)
+                ));
+            }
+        }
+        out.push_str(&format!("{:gutter$} |\n", ""));
+        self.push_notes(&mut out, err, gutter);
+
+        out
+    }
+
+    fn push_notes(&self, out: &mut String, err: &Error, gutter: usize) {
+        if let Some(username) = &err.username {
+            out.push_str(&format!(
+                "{:gutter$} = note: in artist `{}`\n",
+                "", username
+            ));
+        }
+        if let Some(help) = self.help(&err.kind) {
+            out.push_str(&format!("{:gutter$} = help: {}\n", "", help));
+        }
+    }
+
+    fn help(&self, kind: &ErrorKind) -> Option<String> {
+        match kind {
+            ErrorKind::UnsupportedSocialCode(code) | ErrorKind::UnsupportedAvatarCode(code) => {
+                let closest = match kind {
+                    ErrorKind::UnsupportedAvatarCode(_) => {
+                        self.supported_socials.closest_avatar(code, 3)
+                    }
+                    _ => self.supported_socials.closest(code, 3),
+                };
+                let closest = closest
+                    .iter()
+                    .map(|code| format!("`{}`", code))
+                    .collect::<Vec<String>>();
+                if closest.is_empty() {
+                    return None;
+                }
+                Some(format!("closest supported codes: {}", closest.join(", ")))
+            }
+            ErrorKind::AvatarOnlyCode(code) => Some(format!(
+                "use it as the avatar on the info line: `<username>,<display name>,<id>@{}`",
+                code
+            )),
+            ErrorKind::UnknownVariant(code, _) => {
+                let variants = self
+                    .supported_socials
+                    .variants(code)
+                    .iter()
+                    .map(|variant| format!("`{}`", variant))
+                    .collect::<Vec<String>>();
+                match variants.is_empty() {
+                    true => Some(format!("`{}` has no variants, remove the `:`", code)),
+                    false => Some(format!("variants of `{}`: {}", code, variants.join(", "))),
+                }
+            }
+            ErrorKind::MissingSocialCode => Some(
+                "socials are `username@code[,description]` or `//link,description`".to_string(),
+            ),
+            ErrorKind::InvalidEscape => {
+                Some("escapes are `\\,`, `\\\\`, `\\*`, `\\@`, `\\#` and `\\n`".to_string())
+            }
+            ErrorKind::MissingDescription => {
+                Some("`//link` socials need a description: `//link,description`".to_string())
+            }
+            ErrorKind::MissingInstance => {
+                Some("fediverse handles are `user@instance@fedi`".to_string())
+            }
+            ErrorKind::InvalidInstance(_) => {
+                Some("instances are hostnames, like `mastodon.art`".to_string())
+            }
+            ErrorKind::MissingAvatarFile(_) => {
+                Some("`/path` avatars are relative to the avatars dir, `--avatars-dir`".to_string())
+            }
+            ErrorKind::EmptyArtistBlock | ErrorKind::MissingUsername => Some(
+                "the first line of a block is `<username>,<display name>[,<avatar>,<alias>...]`"
+                    .to_string(),
+            ),
+            ErrorKind::DuplicateUsername(_) => {
+                Some("merge the two blocks, or rename one of them".to_string())
+            }
+            ErrorKind::DuplicateAlias(_) => {
+                Some("an alias can only point to one artist, it's dropped here".to_string())
+            }
+            ErrorKind::DuplicateSocial(_) => Some(
+                "codes like `x` and `twitter` are the same platform, remove one of the lines"
+                    .to_string(),
+            ),
+            ErrorKind::UnnormalizedUsername(_) => {
+                Some("`--format --fix-usernames` rewrites it".to_string())
+            }
+            ErrorKind::InvalidUsername(_) => {
+                Some("check the profile link, the username is likely a typo".to_string())
+            }
+            ErrorKind::InvalidAvatar(_) => {
+                Some("avatars are `username@code`, `//link`, `/path` or `_`".to_string())
+            }
+            ErrorKind::NoAvatarSource => Some(
+                "provide an avatar on the info line, or a social supported by unavatar".to_string(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::process_artists::Artists;
+
+    #[test]
+    fn render_unsupported_code() {
+        let source = "bar,Bar\nbar@twitter\n\nfoo,Foo\nfoo@instagram\nfoo@twiter\nfoo@reddit\n";
+        let supported_socials = Rc::new(SupportedSocials::default());
+        let artists = Artists::parse(supported_socials.clone(), source);
+        let err = artists.errors().next().unwrap();
+
+        let rendered = Renderer::new(source, "artists.txt", &supported_socials).render(err);
+        assert_eq!(
+            rendered,
+            "error: unsupported social code `twiter`
+ --> artists.txt:6:5
+  |
+4 | foo,Foo
+5 | foo@instagram
+6 | foo@twiter
+  |     ^^^^^^
+7 | foo@reddit
+  |
+  = note: in artist `foo`
+  = help: closest supported codes: `twitter`, `twitch`
+"
+        );
+    }
+}
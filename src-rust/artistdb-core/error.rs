@@ -0,0 +1,180 @@
+use std::{fmt, ops::Range};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Something got dropped from the output
+    Error,
+    /// The output is still complete, but likely not what was intended
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// `username@code` where `code` isn't in SupportedSocials
+    UnsupportedSocialCode(String),
+    /// `username@code` where `code` only provides avatars, not profile pages
+    AvatarOnlyCode(String),
+    /// `username@code:variant` where the platform has no such variant
+    UnknownVariant(String, String),
+    /// Neither `username@code` nor `//link`
+    MissingSocialCode,
+    /// A `\` with nothing to escape
+    InvalidEscape,
+    /// A `//link` social without a description
+    MissingDescription,
+    /// A fediverse handle without its `@instance`
+    MissingInstance,
+    /// A fediverse instance that isn't a valid hostname
+    InvalidInstance(String),
+    /// A `/path` avatar that isn't a file in the avatars dir
+    MissingAvatarFile(String),
+    /// An artist block without an info line
+    EmptyArtistBlock,
+    /// An info line without a username
+    MissingUsername,
+    /// The username is already used by another artist
+    DuplicateUsername(String),
+    /// The alias is already used by another artist, it's dropped
+    DuplicateAlias(String),
+    /// The same account on the same platform is listed twice, aliases included
+    DuplicateSocial(String),
+    /// The username isn't written the way the platform normalizes it
+    UnnormalizedUsername(String),
+    /// The username can't exist on the platform, the link is likely dead
+    InvalidUsername(String),
+    /// The avatar is neither `username@code`, `//link` nor `/path`
+    InvalidAvatar(String),
+    /// `username@code` avatar where `code` isn't supported by unavatar
+    UnsupportedAvatarCode(String),
+    /// No avatar provided, and none of the socials can be used to infer one
+    NoAvatarSource,
+}
+
+impl ErrorKind {
+    pub fn severity(&self) -> Severity {
+        match self {
+            ErrorKind::UnsupportedSocialCode(_)
+            | ErrorKind::AvatarOnlyCode(_)
+            | ErrorKind::UnknownVariant(_, _)
+            | ErrorKind::MissingSocialCode
+            | ErrorKind::InvalidEscape
+            | ErrorKind::MissingDescription
+            | ErrorKind::MissingInstance
+            | ErrorKind::InvalidInstance(_)
+            | ErrorKind::MissingAvatarFile(_)
+            | ErrorKind::EmptyArtistBlock
+            | ErrorKind::MissingUsername => Severity::Error,
+            ErrorKind::DuplicateUsername(_)
+            | ErrorKind::DuplicateAlias(_)
+            | ErrorKind::DuplicateSocial(_)
+            | ErrorKind::UnnormalizedUsername(_)
+            | ErrorKind::InvalidUsername(_)
+            | ErrorKind::InvalidAvatar(_)
+            | ErrorKind::UnsupportedAvatarCode(_)
+            | ErrorKind::NoAvatarSource => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::UnsupportedSocialCode(code) => {
+                write!(f, "unsupported social code `{}`", code)
+            }
+            ErrorKind::AvatarOnlyCode(code) => {
+                write!(f, "`{}` is an avatar provider, not a social", code)
+            }
+            ErrorKind::UnknownVariant(code, variant) => {
+                write!(f, "unknown variant `{}` for `{}`", variant, code)
+            }
+            ErrorKind::MissingSocialCode => write!(f, "missing social code"),
+            ErrorKind::InvalidEscape => write!(f, "invalid escape"),
+            ErrorKind::MissingDescription => write!(f, "missing description"),
+            ErrorKind::MissingInstance => write!(f, "missing fediverse instance"),
+            ErrorKind::InvalidInstance(instance) => {
+                write!(f, "invalid fediverse instance `{}`", instance)
+            }
+            ErrorKind::MissingAvatarFile(path) => write!(f, "missing avatar file `{}`", path),
+            ErrorKind::EmptyArtistBlock => write!(f, "empty artist block"),
+            ErrorKind::MissingUsername => write!(f, "missing username"),
+            ErrorKind::DuplicateUsername(username) => {
+                write!(f, "duplicate username `{}`", username)
+            }
+            ErrorKind::DuplicateAlias(alias) => write!(f, "duplicate alias `{}`", alias),
+            ErrorKind::DuplicateSocial(social) => write!(f, "duplicate social `{}`", social),
+            ErrorKind::UnnormalizedUsername(username) => {
+                write!(f, "username should be written `{}`", username)
+            }
+            ErrorKind::InvalidUsername(problem) => write!(f, "invalid username, {}", problem),
+            ErrorKind::InvalidAvatar(avatar) => write!(f, "invalid avatar format `{}`", avatar),
+            ErrorKind::UnsupportedAvatarCode(code) => {
+                write!(f, "social code `{}` isn't supported by unavatar", code)
+            }
+            ErrorKind::NoAvatarSource => write!(f, "no supported socials found for the avatar"),
+        }
+    }
+}
+
+/// An error located in the artists.txt source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    pub kind: ErrorKind,
+    /// Byte range in the source
+    pub span: Range<usize>,
+    /// 1-based line number in the source
+    pub line: usize,
+    /// The artist the error belongs to, if known
+    pub username: Option<String>,
+}
+
+impl Error {
+    /// A new error on the first line of whatever is being parsed
+    pub fn new(kind: ErrorKind, span: Range<usize>) -> Error {
+        Error {
+            kind,
+            span,
+            line: 1,
+            username: None,
+        }
+    }
+
+    /// Move the error from a line/block-relative position to an outer one,
+    /// `offset` in bytes and `lines` in lines
+    pub fn shift(mut self, offset: usize, lines: usize) -> Error {
+        self.span = self.span.start + offset..self.span.end + offset;
+        self.line += lines;
+        self
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+
+    pub fn with_username(mut self, username: &str) -> Error {
+        if self.username.is_none() && !username.is_empty() {
+            self.username = Some(username.to_string());
+        }
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.username {
+            Some(username) => write!(f, "line {}: {}: {}", self.line, username, self.kind),
+            None => write!(f, "line {}: {}", self.line, self.kind),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
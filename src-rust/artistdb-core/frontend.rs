@@ -0,0 +1,294 @@
+//! The per-artist file the site reads, one per username:
+//!
+//! ```text
+//! artistdb <version>
+//! <display name>,<colour>,<blurhash>,<fallback avatar>[,<avatar>...]
+//! <*?><link>,<description>
+//! ...
+//! ```
+//!
+//! Avatars are URLs ready to load, tried in order, the fallback one is shown
+//! when none of them loads. Local avatars come in several widths, written as
+//! `<url> <width>w` pairs separated by spaces, like a `srcset`. `<colour>` (`#rrggbb`) and `<blurhash>` are
+//! painted until the first avatar loads, empty if unknown.
+//!
+//! A leading `*` marks the social as special. In every field `\`, `,`, `\n`
+//! and `\r` are escaped as `\\`, `\,`, `\n` and `\r`, a leading `*` as `\*`.
+//! Alias files aren't in this format, they only contain `@<username>`.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+pub const FORMAT_VERSION: u32 = 4;
+const HEADER_PREFIX: &str = "artistdb ";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrontendArtist {
+    pub display_name: String,
+    pub placeholder: Option<AvatarPlaceholder>,
+    pub fallback_avatar: String,
+    pub avatars: Vec<String>,
+    pub socials: Vec<FrontendSocial>,
+}
+
+/// What the page paints until the avatar loads
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AvatarPlaceholder {
+    /// `#rrggbb`
+    pub colour: String,
+    pub blurhash: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrontendSocial {
+    pub is_special: bool,
+    pub link: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The first line isn't the format's `<name> <version>` header
+    MissingHeader,
+    UnsupportedVersion(String),
+    /// No info line after the header
+    MissingInfo,
+    /// A `\` followed by something that isn't escapable, on the nth line
+    InvalidEscape(usize),
+    /// Not the number of fields expected on the nth line
+    InvalidFieldCount(usize),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::MissingHeader => write!(f, "missing format header"),
+            DecodeError::UnsupportedVersion(version) => {
+                write!(f, "unsupported format version `{}`", version)
+            }
+            DecodeError::MissingInfo => write!(f, "missing info line"),
+            DecodeError::InvalidEscape(line) => write!(f, "line {}: invalid escape", line),
+            DecodeError::InvalidFieldCount(line) => {
+                write!(f, "line {}: invalid number of fields", line)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+pub(crate) fn encode_field(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for (i, c) in value.chars().enumerate() {
+        match c {
+            '\\' => encoded.push_str("\\\\"),
+            ',' => encoded.push_str("\\,"),
+            '\n' => encoded.push_str("\\n"),
+            '\r' => encoded.push_str("\\r"),
+            '*' if i == 0 => encoded.push_str("\\*"),
+            _ => encoded.push(c),
+        }
+    }
+    encoded
+}
+
+/// Split by unescaped `,` and resolve the escapes, None on invalid escapes
+pub(crate) fn decode_fields(line: &str) -> Option<Vec<String>> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        let current = fields.last_mut()?;
+        match c {
+            '\\' => match chars.next()? {
+                'n' => current.push('\n'),
+                'r' => current.push('\r'),
+                escaped @ ('\\' | ',' | '*') => current.push(escaped),
+                _ => return None,
+            },
+            ',' => fields.push(String::new()),
+            _ => current.push(c),
+        }
+    }
+    Some(fields)
+}
+
+impl FrontendSocial {
+    pub fn encode(&self) -> String {
+        format!(
+            "{}{},{}",
+            if self.is_special { "*" } else { "" },
+            encode_field(&self.link),
+            encode_field(&self.description)
+        )
+    }
+}
+
+impl FrontendArtist {
+    pub fn encode(&self) -> String {
+        let mut lines = vec![
+            format!("{}{}", HEADER_PREFIX, FORMAT_VERSION),
+            self.encode_info(),
+        ];
+        lines.extend(self.socials.iter().map(|social| social.encode()));
+        lines.join("\n")
+    }
+
+    pub fn encode_info(&self) -> String {
+        let placeholder = self.placeholder.clone().unwrap_or_default();
+        [
+            &self.display_name,
+            &placeholder.colour,
+            &placeholder.blurhash,
+            &self.fallback_avatar,
+        ]
+        .into_iter()
+        .chain(&self.avatars)
+        .map(|field| encode_field(field))
+        .collect::<Vec<String>>()
+        .join(",")
+    }
+
+    pub fn decode(raw: &str) -> Result<FrontendArtist, DecodeError> {
+        let mut lines = raw.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+        let version = lines
+            .next()
+            .and_then(|(_, header)| header.strip_prefix(HEADER_PREFIX))
+            .ok_or(DecodeError::MissingHeader)?;
+        if version != FORMAT_VERSION.to_string() {
+            return Err(DecodeError::UnsupportedVersion(version.to_string()));
+        }
+
+        let fields_of = |i: usize, line: &str, count: usize, variadic: bool| {
+            let fields = decode_fields(line).ok_or(DecodeError::InvalidEscape(i))?;
+            match fields.len() == count || (variadic && fields.len() > count) {
+                true => Ok(fields),
+                false => Err(DecodeError::InvalidFieldCount(i)),
+            }
+        };
+
+        let (i, info) = lines.next().ok_or(DecodeError::MissingInfo)?;
+        let mut info = fields_of(i, info, 4, true)?.into_iter();
+
+        let socials = lines
+            .map(|(i, line)| {
+                let (is_special, line) = match line.strip_prefix('*') {
+                    Some(line) => (true, line),
+                    None => (false, line),
+                };
+                let mut fields = fields_of(i, line, 2, false)?.into_iter();
+                Ok(FrontendSocial {
+                    is_special,
+                    link: fields.next().unwrap_or_default(),
+                    description: fields.next().unwrap_or_default(),
+                })
+            })
+            .collect::<Result<Vec<FrontendSocial>, DecodeError>>()?;
+
+        Ok(FrontendArtist {
+            display_name: info.next().unwrap_or_default(),
+            placeholder: match (info.next(), info.next()) {
+                (Some(colour), Some(blurhash)) if !colour.is_empty() || !blurhash.is_empty() => {
+                    Some(AvatarPlaceholder { colour, blurhash })
+                }
+                _ => None,
+            },
+            fallback_avatar: info.next().unwrap_or_default(),
+            avatars: info.collect(),
+            socials,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{process_artists::Artists, supported_socials::SupportedSocials};
+
+    #[test]
+    fn round_trip_artists() {
+        let raw = "foo,Foo\\, the \\\\ one\\n🇻🇳,//example.com/a\\,b.png\nfoo@instagram,Commissions\\, open\n*//example.com/*,\\*Shop\\, 2\\nlines\n\nbar,Bar\nbar@twitter\nbar@linktr.ee";
+        let artists = Artists::parse(Rc::new(SupportedSocials::default()), raw);
+        assert_eq!(artists.len(), 2);
+
+        for artist in &artists {
+            let expected = artist.to_frontend().unwrap();
+            let decoded = FrontendArtist::decode(&artist.serialize().unwrap()).unwrap();
+            assert_eq!(decoded, expected);
+        }
+
+        let foo = artists.get("foo").unwrap().to_frontend().unwrap();
+        assert_eq!(foo.display_name, "Foo, the \\ one\n🇻🇳");
+        assert_eq!(foo.avatars[0], "//example.com/a,b.png");
+        assert_eq!(foo.socials[0].description, "Instagram | Commissions, open");
+        assert_eq!(foo.socials[1].description, "*Shop, 2\nlines");
+        assert!(foo.socials[1].is_special);
+    }
+
+    #[test]
+    fn encode_escapes() {
+        let artist = FrontendArtist {
+            display_name: "*A, B".to_string(),
+            placeholder: Some(AvatarPlaceholder {
+                colour: "#336699".to_string(),
+                blurhash: "LEHV6nWB2yk8pyo0adR*.7kCMdnj".to_string(),
+            }),
+            fallback_avatar: "/avatar.svg".to_string(),
+            avatars: vec!["//a.com/1,2.png".to_string(), "/avatars/*.png".to_string()],
+            socials: vec![FrontendSocial {
+                is_special: true,
+                link: "//a.com/x,y".to_string(),
+                description: "c\\d\r\n".to_string(),
+            }],
+        };
+        assert_eq!(
+            artist.encode(),
+            "artistdb 4\n\\*A\\, B,#336699,LEHV6nWB2yk8pyo0adR*.7kCMdnj,/avatar.svg,//a.com/1\\,2.png,/avatars/*.png\n*//a.com/x\\,y,c\\\\d\\r\\n"
+        );
+        assert_eq!(FrontendArtist::decode(&artist.encode()).unwrap(), artist);
+
+        let artist = FrontendArtist {
+            placeholder: None,
+            avatars: vec![],
+            socials: vec![],
+            ..artist
+        };
+        assert_eq!(artist.encode(), "artistdb 4\n\\*A\\, B,,,/avatar.svg");
+        assert_eq!(FrontendArtist::decode(&artist.encode()).unwrap(), artist);
+    }
+
+    #[test]
+    fn decode_invalid() {
+        assert_eq!(
+            FrontendArtist::decode("Foo,_").unwrap_err(),
+            DecodeError::MissingHeader
+        );
+        assert_eq!(
+            FrontendArtist::decode("artistdb 0\nFoo,_").unwrap_err(),
+            DecodeError::UnsupportedVersion("0".to_string())
+        );
+        assert_eq!(
+            FrontendArtist::decode("artistdb 3\nFoo,_").unwrap_err(),
+            DecodeError::UnsupportedVersion("3".to_string())
+        );
+        assert_eq!(
+            FrontendArtist::decode("artistdb 4").unwrap_err(),
+            DecodeError::MissingInfo
+        );
+        assert_eq!(
+            FrontendArtist::decode("artistdb 4\nFoo,,").unwrap_err(),
+            DecodeError::InvalidFieldCount(2)
+        );
+        assert_eq!(
+            FrontendArtist::decode("artistdb 4\nFoo,,,_\n//a.com,b,c").unwrap_err(),
+            DecodeError::InvalidFieldCount(3)
+        );
+        assert_eq!(
+            FrontendArtist::decode("artistdb 4\nFoo\\x,,,_").unwrap_err(),
+            DecodeError::InvalidEscape(2)
+        );
+    }
+}
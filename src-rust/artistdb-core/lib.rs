@@ -0,0 +1,144 @@
+//! Parser, data model and serializers for the artists.txt database.
+//!
+//! - Parse: [`Artists::parse`] from a `&str`, [`Artists::from_reader`] or
+//!   [`Artists::from_file`]
+//! - Query: [`Artists::iter`], [`Artists::get`] by username or alias
+//! - Serialize: [`Artist::serialize`] to the frontend format, read back with
+//!   [`FrontendArtist::decode`], [`Artists::format`] back to the artists.txt
+//!   format, [`SearchIndex::new`] to the index the landing page searches
+//! - Report: [`Artists::errors`], rendered with [`Renderer`]
+//! - Registry: [`SupportedSocials::default`] from the bundled `socials.toml`,
+//!   extended with [`SupportedSocials::from_path`]
+
+pub mod avatar_providers;
+pub mod diagnostic;
+pub mod error;
+pub mod frontend;
+pub mod process_artists;
+pub mod process_avatar;
+pub mod process_format;
+pub mod process_info;
+pub mod process_socials;
+pub mod process_url;
+pub mod process_username;
+pub mod search_index;
+pub mod supported_socials;
+pub mod tokenizer;
+
+pub use avatar_providers::{
+    AvatarProvider, AvatarProviders, AvatarSource, Direct, Gravatar, Local, PlaceholderCache,
+    Unavatar,
+};
+pub use diagnostic::Renderer;
+pub use error::{Error, ErrorKind, Severity};
+pub use frontend::{AvatarPlaceholder, DecodeError, FrontendArtist, FrontendSocial};
+pub use process_artists::{Artist, Artists};
+pub use process_format::{FormatError, FormatOptions};
+pub use process_socials::Social;
+pub use process_url::Variant;
+pub use process_username::{Normalize, UsernameRule};
+pub use search_index::{IndexEntry, SearchIndex};
+pub use supported_socials::{Platform, RegistryError, RegistryIssue, SupportedSocials};
+
+pub(crate) const BOM: char = '\u{feff}';
+
+/// Split into blocks separated by runs of blank lines, skipping a leading
+/// BOM. Each block comes with its byte offset and the number of lines before it
+pub(crate) fn split_blocks(raw: &str) -> Vec<(usize, usize, &str)> {
+    let bom = match raw.starts_with(BOM) {
+        true => BOM.len_utf8(),
+        false => 0,
+    };
+
+    let mut blocks = vec![];
+    let mut current: Option<(usize, usize, usize)> = None;
+    for (i, (offset, line)) in lines_with_offsets(&raw[bom..]).enumerate() {
+        let offset = offset + bom;
+        match (&mut current, line.trim().is_empty()) {
+            (Some((_, _, end)), false) => *end = offset + line.len(),
+            (None, false) => current = Some((offset, i, offset + line.len())),
+            (Some((start, lines, end)), true) => {
+                blocks.push((*start, *lines, &raw[*start..*end]));
+                current = None;
+            }
+            (None, true) => {}
+        }
+    }
+    if let Some((start, lines, end)) = current {
+        blocks.push((start, lines, &raw[start..end]));
+    }
+
+    blocks
+}
+
+/// Split a line into its content and its comment. A comment starts with an
+/// unescaped `#` at the start of the line, or with a ` # ` (a `#` between a
+/// whitespace and a whitespace or the line end), so `My #1 fan` is kept whole
+pub(crate) fn strip_comment(line: &str) -> (&str, Option<&str>) {
+    let mut previous: Option<char> = None;
+    let mut escaped = false;
+    let mut chars = line.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '#' if line[..i].trim().is_empty() => return ("", Some(&line[i..])),
+            '#' if previous.is_some_and(char::is_whitespace)
+                && chars.peek().is_none_or(|(_, next)| next.is_whitespace()) =>
+            {
+                return (line[..i].trim_end(), Some(&line[i..]));
+            }
+            _ => {}
+        }
+        previous = Some(c);
+    }
+    (line.trim_end(), None)
+}
+
+/// Lines without their `\n`/`\r\n` ending, each with its byte offset
+pub(crate) fn lines_with_offsets(raw: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut offset = 0;
+    raw.split_inclusive('\n').map(move |line| {
+        let start = offset;
+        offset += line.len();
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        (start, line.strip_suffix('\r').unwrap_or(line))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_blocks_tolerant() {
+        let raw = "\u{feff}foo,Foo\r\nfoo@twitter\r\n\r\n \t\n\n\nbar,Bar\n";
+        let blocks = split_blocks(raw);
+        assert_eq!(
+            blocks,
+            vec![(3, 0, "foo,Foo\r\nfoo@twitter"), (32, 6, "bar,Bar")]
+        );
+    }
+
+    #[test]
+    fn strip_comments() {
+        assert_eq!(strip_comment("# comment"), ("", Some("# comment")));
+        assert_eq!(
+            strip_comment("  foo@twitter # main"),
+            ("  foo@twitter", Some("# main"))
+        );
+        assert_eq!(
+            strip_comment("//example.com/#about,Website"),
+            ("//example.com/#about,Website", None)
+        );
+        assert_eq!(
+            strip_comment("foo,\\#1 fan \\\\#2 # comment"),
+            ("foo,\\#1 fan \\\\#2", Some("# comment"))
+        );
+        assert_eq!(
+            strip_comment("foo,My #1 fan #"),
+            ("foo,My #1 fan", Some("#"))
+        );
+        assert_eq!(strip_comment("  #disabled"), ("", Some("#disabled")));
+    }
+}
@@ -0,0 +1,529 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Read},
+    ops::Range,
+    path::Path,
+    rc::Rc,
+};
+
+use crate::{
+    avatar_providers::{AvatarProviders, AvatarSource},
+    error::{Error, ErrorKind},
+    frontend::{FrontendArtist, FrontendSocial},
+    lines_with_offsets,
+    process_socials::Social,
+    split_blocks, strip_comment,
+    supported_socials::SupportedSocials,
+    BOM,
+};
+
+#[derive(Debug)]
+pub struct Artist {
+    pub username: String,
+    pub display_name: Option<String>,
+    pub avatar: Option<String>,
+    pub alias: Vec<String>,
+    pub socials: Vec<Social>,
+
+    pub(crate) raw_info_line: String,
+    pub(crate) raw_social_lines: Vec<String>,
+    pub(crate) formatted_info_line: String,
+    errors: Vec<Error>,
+    /// Comment lines before the info line, and the comment after it
+    pub(crate) leading_comments: Vec<String>,
+    pub(crate) info_comment: Option<String>,
+    /// Where each component of the info line is in the source
+    pub(crate) info_spans: Vec<Range<usize>>,
+    pub(crate) info_line: usize,
+    pub(crate) original_avatar: Option<String>,
+    /// Blocks right after this one that failed to parse or only hold
+    /// comments, verbatim, kept after it by the formatter
+    pub(crate) trailing_blocks: Vec<String>,
+    pub(crate) supported_socials: Rc<SupportedSocials>,
+}
+
+impl Default for Artist {
+    fn default() -> Artist {
+        Artist {
+            display_name: None,
+            avatar: None,
+            alias: vec![],
+            socials: vec![],
+
+            raw_info_line: "".to_string(),
+            raw_social_lines: vec![],
+            formatted_info_line: "".to_string(),
+            errors: vec![],
+            leading_comments: vec![],
+            info_comment: None,
+            info_spans: vec![],
+            info_line: 0,
+            original_avatar: None,
+            trailing_blocks: vec![],
+            username: "".to_string(),
+            supported_socials: Rc::new(SupportedSocials::default()),
+        }
+    }
+}
+
+impl Artist {
+    /// Parse an artist block, the info line followed by the social lines.
+    /// Socials failed to parse are skipped and kept in errors()
+    pub fn parse(supported_socials: Rc<SupportedSocials>, raw: &str) -> Result<Artist, Error> {
+        Artist::parse_at(supported_socials, raw, 0, 0)
+    }
+
+    /// Same as parse(), with errors located `offset` bytes and `lines` lines
+    /// into the source
+    pub(crate) fn parse_at(
+        supported_socials: Rc<SupportedSocials>,
+        raw: &str,
+        offset: usize,
+        lines: usize,
+    ) -> Result<Artist, Error> {
+        let mut new = Artist {
+            supported_socials,
+            ..Default::default()
+        };
+
+        let raw_lines = lines_with_offsets(raw).collect::<Vec<(usize, &str)>>();
+
+        // Comments before the info line stick with the artist
+        let info_index = raw_lines
+            .iter()
+            .position(|(_, line)| !strip_comment(line).0.is_empty())
+            .ok_or_else(|| {
+                Error::new(ErrorKind::EmptyArtistBlock, 0..raw.len()).shift(offset, lines)
+            })?;
+        new.leading_comments = raw_lines[..info_index]
+            .iter()
+            .map(|(_, line)| line.to_string())
+            .collect();
+
+        let (info_offset, info_line) = raw_lines[info_index];
+        let (info, info_comment) = strip_comment(info_line);
+        new.raw_info_line = info_line.to_string();
+        new.info_comment = info_comment.map(|comment| comment.to_string());
+        new.parse_info(info)
+            .map_err(|err| err.shift(offset + info_offset, lines + info_index))?;
+        new.info_spans = new
+            .info_spans
+            .iter()
+            .map(|span| span.start + offset + info_offset..span.end + offset + info_offset)
+            .collect();
+        new.info_line = lines + info_index + 1;
+        raw_lines
+            .iter()
+            .enumerate()
+            .skip(info_index + 1)
+            .for_each(|(i, (line_offset, line))| {
+                new.raw_social_lines.push(line.to_string());
+                let (line, _) = strip_comment(line);
+                if line.is_empty() {
+                    return;
+                }
+                let mut social = Social::new(Rc::clone(&new.supported_socials));
+                match social.parse(line) {
+                    Ok(_) => {
+                        let warnings = std::mem::take(&mut social.warnings);
+                        new.errors.extend(warnings.into_iter().map(|err| {
+                            err.shift(offset + line_offset, lines + i)
+                                .with_username(&new.username)
+                        }));
+                        social.span = offset + line_offset..offset + line_offset + line.len();
+                        social.line = lines + i + 1;
+                        new.socials.push(social);
+                    }
+                    Err(err) => {
+                        let err = err
+                            .shift(offset + line_offset, lines + i)
+                            .with_username(&new.username);
+                        new.errors.push(err);
+                    }
+                }
+            });
+
+        new.formatted_info_line = new.serialize_info_for_original()?;
+
+        Ok(new)
+    }
+
+    /// Serialize to the frontend format, see [`crate::frontend`]
+    pub fn serialize(&self) -> Result<String, Error> {
+        self.serialize_with(&AvatarProviders::default())
+    }
+
+    /// Same as serialize(), with the avatar URLs from `providers`
+    pub fn serialize_with(&self, providers: &AvatarProviders) -> Result<String, Error> {
+        Ok(self.to_frontend_with(providers)?.encode())
+    }
+
+    pub fn to_frontend(&self) -> Result<FrontendArtist, Error> {
+        self.to_frontend_with(&AvatarProviders::default())
+    }
+
+    pub fn to_frontend_with(&self, providers: &AvatarProviders) -> Result<FrontendArtist, Error> {
+        let socials = self
+            .socials
+            .iter()
+            .map(|social| {
+                social
+                    .to_frontend()
+                    .map_err(|err| err.with_username(&self.username))
+            })
+            .collect::<Result<Vec<FrontendSocial>, Error>>()?;
+        Ok(FrontendArtist {
+            display_name: self.frontend_display_name(),
+            placeholder: self.avatar_placeholder_with(providers),
+            fallback_avatar: providers.fallback.clone(),
+            avatars: self.serialize_avatars_with(providers),
+            socials,
+        })
+    }
+
+    /// Problems found while parsing and linting that didn't stop the artist
+    /// from parsing
+    pub fn errors(&self) -> &[Error] {
+        &self.errors
+    }
+
+    /// An error pointing at the nth component of the info line
+    pub(crate) fn info_error(&self, kind: ErrorKind, component: usize) -> Error {
+        let span = match (self.info_spans.get(component), self.info_spans.first()) {
+            (Some(span), _) => span.clone(),
+            (None, Some(first)) => first.clone(),
+            (None, None) => 0..0,
+        };
+        Error {
+            kind,
+            span,
+            line: self.info_line,
+            username: None,
+        }
+        .with_username(&self.username)
+    }
+}
+
+pub struct Artists {
+    pub(crate) artists: Vec<Artist>,
+    pub(crate) supported_socials: Rc<SupportedSocials>,
+    errors: Vec<Error>,
+    /// Blocks before the first artist that failed to parse or only hold
+    /// comments, verbatim. The ones after an artist are attached to it
+    pub(crate) leading_blocks: Vec<String>,
+    /// Every non-blank line of the source, with its line number
+    pub(crate) source_lines: Vec<(usize, String)>,
+}
+
+impl Artists {
+    /// Parse the whole artists.txt content, artists failed to parse are skipped
+    /// and kept in errors().
+    /// Blocks are separated by one or more blank lines, `#` starts a comment
+    /// at the start of a line, ` # ` after some content
+    pub fn parse(supported_socials: Rc<SupportedSocials>, raw: &str) -> Artists {
+        let mut new = Artists {
+            artists: Vec::new(),
+            supported_socials,
+            errors: Vec::new(),
+            leading_blocks: Vec::new(),
+            source_lines: lines_with_offsets(raw.strip_prefix(BOM).unwrap_or(raw))
+                .enumerate()
+                .filter(|(_, (_, line))| !line.trim().is_empty())
+                .map(|(i, (_, line))| (i + 1, line.to_string()))
+                .collect(),
+        };
+
+        for (offset, lines, raw_block) in split_blocks(raw) {
+            let raw_lines = raw_block.lines().collect::<Vec<&str>>();
+
+            // Comment-only blocks aren't artists, but are kept as-is
+            if raw_lines
+                .iter()
+                .all(|line| strip_comment(line).0.is_empty())
+            {
+                new.keep_raw_block(raw_lines.join("\n"));
+                continue;
+            }
+
+            match Artist::parse_at(new.supported_socials.clone(), raw_block, offset, lines) {
+                Ok(artist) => new.artists.push(artist),
+                Err(err) => {
+                    new.keep_raw_block(raw_lines.join("\n"));
+                    new.errors.push(err);
+                }
+            }
+        }
+
+        new.lint_and_format();
+        new
+    }
+
+    /// Attach a block that isn't an artist to the artist before it, so it
+    /// keeps its neighbours once artists are sorted
+    fn keep_raw_block(&mut self, raw: String) {
+        match self.artists.last_mut() {
+            Some(artist) => artist.trailing_blocks.push(raw),
+            None => self.leading_blocks.push(raw),
+        }
+    }
+
+    /// Same as parse(), reading the content from a reader
+    pub fn from_reader(
+        supported_socials: Rc<SupportedSocials>,
+        mut reader: impl Read,
+    ) -> io::Result<Artists> {
+        let mut raw_data = String::new();
+        reader.read_to_string(&mut raw_data)?;
+        Ok(Artists::parse(supported_socials, &raw_data))
+    }
+
+    /// Same as parse(), reading the content from a file
+    pub fn from_file(
+        supported_socials: Rc<SupportedSocials>,
+        path: impl AsRef<Path>,
+    ) -> io::Result<Artists> {
+        let raw_data = std::fs::read_to_string(path)?;
+        Ok(Artists::parse(supported_socials, &raw_data))
+    }
+
+    /// Warn duplicate users and unresolvable avatars, remove alias duplicates
+    pub fn lint_and_format(&mut self) {
+        let mut all_username = HashSet::new();
+        let supported_socials = Rc::clone(&self.supported_socials);
+        self.artists.iter_mut().for_each(|artist| {
+            if all_username.contains(&artist.username) {
+                let err =
+                    artist.info_error(ErrorKind::DuplicateUsername(artist.username.clone()), 0);
+                artist.errors.push(err);
+            }
+            all_username.insert(artist.username.clone());
+        });
+
+        let mut alias_count: HashMap<String, usize> = HashMap::new();
+        self.artists
+            .iter()
+            .flat_map(|artist| artist.alias.iter().collect::<HashSet<&String>>())
+            .for_each(|alias| *alias_count.entry(alias.clone()).or_default() += 1);
+        let is_duplicate = |alias: &String| alias_count[alias] > 1 || all_username.contains(alias);
+
+        self.artists.iter_mut().for_each(|artist| {
+            let duplicates = artist
+                .alias
+                .iter()
+                .enumerate()
+                .filter(|(_, alias)| is_duplicate(alias))
+                .map(|(i, alias)| (i, alias.clone()))
+                .collect::<Vec<(usize, String)>>();
+            duplicates.into_iter().for_each(|(i, alias)| {
+                // Aliases start from the 4th component of the info line
+                let err = artist.info_error(ErrorKind::DuplicateAlias(alias), i + 3);
+                artist.errors.push(err);
+            });
+
+            // Remove duplicates from the current
+            artist.alias.retain(|alias| !is_duplicate(alias));
+
+            let mut seen_socials = HashSet::new();
+            let duplicates = artist
+                .socials
+                .iter()
+                .filter_map(|social| match (social.get_code(), social.get_name()) {
+                    (Some(code), Some(name)) => {
+                        // Only as case-insensitive as the platform
+                        let name = supported_socials.normalize_username(&code, &name);
+                        let key = format!("{}@{}", name, code);
+                        (!seen_socials.insert(key.clone())).then(|| Error {
+                            kind: ErrorKind::DuplicateSocial(key),
+                            span: social.span.clone(),
+                            line: social.line,
+                            username: None,
+                        })
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<Error>>();
+            artist.errors.extend(
+                duplicates
+                    .into_iter()
+                    .map(|err| err.with_username(&artist.username)),
+            );
+
+            let (_, avatar_errors) = artist.resolve_avatars();
+            artist.errors.extend(avatar_errors);
+        });
+
+        self.artists.sort_by(|a, b| a.username.cmp(&b.username));
+    }
+
+    /// Error on `/path` avatars that aren't a file under `dir`
+    pub fn check_avatar_files(&mut self, dir: &Path) {
+        self.artists.iter_mut().for_each(|artist| {
+            let missing = artist
+                .avatar_sources()
+                .into_iter()
+                .filter_map(|source| match source {
+                    AvatarSource::Local(path) if !is_avatar_file(dir, &path) => Some(path),
+                    _ => None,
+                })
+                .map(|path| artist.info_error(ErrorKind::MissingAvatarFile(path), 2))
+                .collect::<Vec<Error>>();
+            artist.errors.extend(missing);
+        });
+    }
+
+    /// Every error, from artists failed to parse and from the skipped socials
+    /// of those that did
+    pub fn errors(&self) -> impl Iterator<Item = &Error> {
+        self.errors
+            .iter()
+            .chain(self.artists.iter().flat_map(|artist| artist.errors.iter()))
+    }
+
+    pub fn get_artists(&self) -> &Vec<Artist> {
+        &self.artists
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Artist> {
+        self.artists.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.artists.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.artists.is_empty()
+    }
+
+    /// Look up an artist by username, then by alias
+    pub fn get(&self, name: &str) -> Option<&Artist> {
+        self.artists
+            .iter()
+            .find(|artist| artist.username == name)
+            .or_else(|| {
+                self.artists
+                    .iter()
+                    .find(|artist| artist.alias.iter().any(|alias| alias == name))
+            })
+    }
+}
+
+impl<'a> IntoIterator for &'a Artists {
+    type Item = &'a Artist;
+    type IntoIter = std::slice::Iter<'a, Artist>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.artists.iter()
+    }
+}
+
+/// Whether `path`, as written on the info line, is a file under `dir`, `..`
+/// can't step out of it
+fn is_avatar_file(dir: &Path, path: &str) -> bool {
+    let relative = Path::new(path.trim_start_matches('/'));
+    relative
+        .components()
+        .all(|component| matches!(component, std::path::Component::Normal(_)))
+        && dir.join(relative).is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RAW: &str = "foo,Foo,_,bar,baz\nfoo@twitter\n\nqux,Qux\nqux@instagram\n//link,Website";
+
+    #[test]
+    fn parse_and_lookup() {
+        let artists = Artists::parse(Rc::new(SupportedSocials::default()), RAW);
+        assert_eq!(artists.len(), 2);
+
+        let usernames = artists
+            .iter()
+            .map(|artist| artist.username.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(usernames, vec!["foo", "qux"]);
+
+        assert_eq!(artists.get("qux").unwrap().socials.len(), 2);
+        assert_eq!(artists.get("baz").unwrap().username, "foo");
+        assert!(artists.get("unknown").is_none());
+    }
+
+    #[test]
+    fn errors_are_located() {
+        let raw = "foo,Foo\nfoo@twitter\nfoo@unknown\n\n,Bar\nbar@twitter";
+        let artists = Artists::parse(Rc::new(SupportedSocials::default()), raw);
+        assert_eq!(artists.len(), 1);
+
+        let errors = artists.errors().collect::<Vec<&Error>>();
+        assert_eq!(errors.len(), 2);
+
+        assert_eq!(errors[0].kind, ErrorKind::MissingUsername);
+        assert_eq!(errors[0].line, 5);
+        assert_eq!(&raw[errors[0].span.clone()], ",Bar");
+
+        assert_eq!(
+            errors[1].kind,
+            ErrorKind::UnsupportedSocialCode("unknown".to_string())
+        );
+        assert_eq!(errors[1].line, 3);
+        assert_eq!(errors[1].username.as_deref(), Some("foo"));
+        assert_eq!(&raw[errors[1].span.clone()], "unknown");
+    }
+
+    #[test]
+    fn check_avatar_files() {
+        let dir = std::env::temp_dir().join(format!("artistdb-avatars-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("sub/foo.png"), b"").unwrap();
+
+        let raw = "foo,Foo,/sub/foo.png\n\nbar,Bar,/bar.png|/../foo.png";
+        let mut artists = Artists::parse(Rc::new(SupportedSocials::default()), raw);
+        artists.check_avatar_files(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let errors = artists
+            .errors()
+            .map(|err| (err.line, err.kind.clone()))
+            .collect::<Vec<(usize, ErrorKind)>>();
+        assert_eq!(
+            errors,
+            vec![
+                (3, ErrorKind::MissingAvatarFile("/bar.png".to_string())),
+                (3, ErrorKind::MissingAvatarFile("/../foo.png".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn lint_duplicates() {
+        let raw = "foo,Foo,_,bar,qux\nfoo@twitter\n\nbaz,Baz,_,bar,foo\nbaz@twitter\n\nfoo,Foo\nfoo@reddit\nfoo@x\nFoo@twitter\nBar@fa\nbar@fa";
+        let artists = Artists::parse(Rc::new(SupportedSocials::default()), raw);
+
+        let mut kinds = artists
+            .errors()
+            .map(|err| (err.line, err.kind.clone()))
+            .collect::<Vec<(usize, ErrorKind)>>();
+        kinds.sort_by_key(|(line, _)| *line);
+        assert_eq!(
+            kinds,
+            vec![
+                (1, ErrorKind::DuplicateAlias("bar".to_string())),
+                (4, ErrorKind::DuplicateAlias("bar".to_string())),
+                (4, ErrorKind::DuplicateAlias("foo".to_string())),
+                (7, ErrorKind::DuplicateUsername("foo".to_string())),
+                (10, ErrorKind::UnnormalizedUsername("foo".to_string())),
+                (10, ErrorKind::DuplicateSocial("foo@twitter".to_string())),
+            ]
+        );
+        assert_eq!(artists.get("baz").unwrap().alias, Vec::<String>::new());
+        assert_eq!(artists.get("qux").unwrap().username, "foo");
+    }
+
+    #[test]
+    fn from_reader() {
+        let supported_socials = Rc::new(SupportedSocials::default());
+        let artists = Artists::from_reader(supported_socials, RAW.as_bytes()).unwrap();
+        assert_eq!(artists.len(), 2);
+    }
+}
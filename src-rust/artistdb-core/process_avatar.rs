@@ -0,0 +1,377 @@
+use crate::{
+    avatar_providers::{AvatarProviders, AvatarSource},
+    error::{Error, ErrorKind},
+    frontend::AvatarPlaceholder,
+    process_artists::Artist,
+};
+
+fn unavatar(social_username: &str, social_code: &str) -> AvatarSource {
+    AvatarSource::Social {
+        code: social_code.to_string(),
+        username: social_username.to_string(),
+    }
+}
+
+/// Unavatar has no fediverse provider, microlink reads the profile page instead
+fn fediverse(handle: &str) -> Option<AvatarSource> {
+    let (user, instance) = handle.split_once('@')?;
+    Some(unavatar(&format!("{}/@{}", instance, user), "microlink"))
+}
+
+/// Transforming the Artist's avatar in raw form into a proper URL for the frontend
+impl Artist {
+    /// The first avatar URL, the fallback one if there's none
+    pub fn serialize_avatar(&self) -> String {
+        let providers = AvatarProviders::default();
+        self.serialize_avatars_with(&providers)
+            .into_iter()
+            .next()
+            .unwrap_or(providers.fallback)
+    }
+
+    /// The avatar URLs the frontend tries in order
+    pub fn serialize_avatars_with(&self, providers: &AvatarProviders) -> Vec<String> {
+        let mut urls: Vec<String> = vec![];
+        self.resolve_avatars()
+            .0
+            .iter()
+            .filter_map(|source| providers.url(source))
+            .for_each(|url| {
+                if !urls.contains(&url) {
+                    urls.push(url);
+                }
+            });
+        urls
+    }
+
+    /// The placeholder of the first avatar the frontend tries
+    pub fn avatar_placeholder_with(
+        &self,
+        providers: &AvatarProviders,
+    ) -> Option<AvatarPlaceholder> {
+        let source = self
+            .resolve_avatars()
+            .0
+            .into_iter()
+            .find(|source| providers.url(source).is_some())?;
+        providers.placeholder(&source)
+    }
+
+    /// Every avatar source, in the order the frontend tries them
+    pub fn avatar_sources(&self) -> Vec<AvatarSource> {
+        self.resolve_avatars().0
+    }
+
+    /// Where the avatar comes from, the info line's `a|b|c` candidates then
+    /// the ones inferred from the socials, with warnings about the
+    /// candidates that can't be used
+    pub(crate) fn resolve_avatars(&self) -> (Vec<AvatarSource>, Vec<Error>) {
+        let supported_socials = self.supported_socials.as_ref();
+        let mut errors = vec![];
+        let mut sources = vec![];
+
+        let candidates = self
+            .avatar
+            .iter()
+            .flat_map(|avatar| avatar.split('|'))
+            .map(|candidate| candidate.trim())
+            .filter(|candidate| !candidate.is_empty() && *candidate != "_");
+        for candidate in candidates {
+            match self.resolve_candidate(candidate) {
+                Ok(source) => sources.push(source),
+                Err(kind) => errors.push(self.info_error(kind, 2)),
+            }
+        }
+
+        // Sorting is stable, unlisted platforms keep the socials' order
+        let mut inferred = self
+            .socials
+            .iter()
+            .filter_map(|social| match (social.get_code(), social.get_name()) {
+                (Some(code), Some(name)) if !name.is_empty() => {
+                    if supported_socials.is_fediverse(&code) {
+                        return Some((code, fediverse(&name)?));
+                    }
+                    if !supported_socials.is_unavatar_supported(&code) {
+                        return None;
+                    }
+                    let source = unavatar(&name, &code);
+                    Some((code, source))
+                }
+                _ => None,
+            })
+            .collect::<Vec<(String, AvatarSource)>>();
+        inferred.sort_by_key(|(code, _)| supported_socials.avatar_rank(code));
+        for (_, source) in inferred {
+            if !sources.contains(&source) {
+                sources.push(source);
+            }
+        }
+
+        // Last resort, the favicon of the artist's own site
+        if sources.is_empty() {
+            sources.extend(self.favicon());
+        }
+
+        if sources.is_empty() && errors.is_empty() {
+            errors.push(self.info_error(ErrorKind::NoAvatarSource, 0));
+        }
+
+        (sources, errors)
+    }
+
+    /// The favicon of the first `//` link, if its domain is allowed
+    fn favicon(&self) -> Option<AvatarSource> {
+        let link = self.socials.iter().find_map(|social| social.get_link())?;
+        let host = link
+            .trim_start_matches("//")
+            .split(['/', '?', '#'])
+            .next()?
+            .rsplit('@')
+            .next()?
+            .split(':')
+            .next()?
+            .to_ascii_lowercase();
+        let code = self.supported_socials.favicon(&host)?;
+        Some(unavatar(&host, code))
+    }
+
+    /// One candidate of the info line: `//link`, `/path` or `username@social`
+    fn resolve_candidate(&self, avatar: &str) -> Result<AvatarSource, ErrorKind> {
+        let supported_socials = self.supported_socials.as_ref();
+
+        // URL
+        if avatar.starts_with("//") {
+            return Ok(AvatarSource::Url(avatar.to_string()));
+        }
+        // From root
+        if avatar.starts_with('/') {
+            return Ok(AvatarSource::Local(avatar.to_string()));
+        }
+
+        let Some((social_username, social_code)) = avatar.rsplit_once('@') else {
+            return Err(ErrorKind::InvalidAvatar(avatar.to_string()));
+        };
+        if supported_socials.is_fediverse(social_code) {
+            let handle = social_username.trim_start_matches('@').to_ascii_lowercase();
+            return fediverse(&handle).ok_or(ErrorKind::MissingInstance);
+        }
+        // Avatar-only providers take emails, domains or URLs
+        if social_username.contains('@') && !supported_socials.is_avatar_only(social_code) {
+            return Err(ErrorKind::InvalidAvatar(avatar.to_string()));
+        }
+        // Emails are hashed offline instead of going through unavatar
+        if supported_socials.canonical(social_code) == Some("gravatar") {
+            return Ok(AvatarSource::Email(social_username.to_string()));
+        }
+        match supported_socials
+            .canonical(social_code)
+            .filter(|code| supported_socials.is_unavatar_supported(code))
+        {
+            Some(code) => Ok(unavatar(social_username, code)),
+            None => Err(ErrorKind::UnsupportedAvatarCode(social_code.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::rc::Rc;
+
+    use crate::{process_socials::Social, supported_socials::SupportedSocials};
+
+    use super::*;
+
+    fn unavatar_url(path: &str) -> String {
+        format!("https://unavatar.io/{}?size=400&fallback=false", path)
+    }
+
+    /// The socials of an artist's lines
+    fn socials(raws: &[&str]) -> Vec<Social> {
+        let supported_socials = Rc::from(SupportedSocials::default());
+        raws.iter()
+            .map(|raw| {
+                Social::new(supported_socials.clone())
+                    .parse_into(raw)
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn serialize_valid() {
+        let supported_socials = Rc::from(SupportedSocials::default());
+        let example_url = "//example.com/avatar.png".to_string();
+
+        // social & name present
+        let mut artist = Artist::default();
+        let social = Social::new(supported_socials.clone());
+        artist.socials = vec![social.parse_into("foo@twitter").unwrap()];
+        assert_eq!(artist.serialize_avatar(), unavatar_url("twitter/foo"));
+
+        // only avatar override
+        let mut artist = Artist::default();
+        artist.avatar = Some(example_url.clone());
+        assert_eq!(artist.serialize_avatar(), example_url);
+
+        // valid provided avatar
+        let mut artist = Artist::default();
+        artist.avatar = Some("foo@twitter".to_string());
+        assert_eq!(artist.serialize_avatar(), unavatar_url("twitter/foo"))
+    }
+
+    #[test]
+    fn serialize_fallback() {
+        let supported_socials = Rc::from(SupportedSocials::default());
+
+        // unavatar support, but no username
+        let mut artist = Artist::default();
+        let social = Social::new(supported_socials.clone());
+        artist.socials = vec![social.parse_into("@twitter").unwrap()];
+        assert_eq!(artist.serialize_avatar(), "/avatar.svg");
+
+        // invalid provided avatar
+        let mut artist = Artist::default();
+        artist.avatar = Some("foo@example".to_string());
+        assert_eq!(artist.serialize_avatar(), "/avatar.svg");
+
+        // invalid provided avatar, auto match from a valid social
+        let mut artist = Artist::default();
+        let social = Social::new(supported_socials.clone());
+        artist.avatar = Some("foo@example".to_string());
+        artist.socials = vec![social.parse_into("foo@reddit").unwrap()];
+        assert_eq!(artist.serialize_avatar(), unavatar_url("reddit/foo"));
+
+        // empty
+        let artist = Artist::default();
+        assert_eq!(artist.serialize_avatar(), "/avatar.svg");
+    }
+
+    #[test]
+    fn serialize_avatar_only() {
+        let mut artist = Artist::default();
+        artist.avatar = Some("me@mail.com@gravatar".to_string());
+        assert_eq!(
+            artist.serialize_avatar(),
+            "https://gravatar.com/avatar/088c1bd6142c57a3b09384fb4c7fc8d4?s=400&d=404"
+        );
+
+        let mut artist = Artist::default();
+        artist.avatar = Some("domain.com@duckduckgo".to_string());
+        assert_eq!(
+            artist.serialize_avatar(),
+            unavatar_url("duckduckgo/domain.com")
+        );
+    }
+
+    #[test]
+    fn serialize_fediverse() {
+        let supported_socials = Rc::from(SupportedSocials::default());
+
+        let mut artist = Artist::default();
+        artist.avatar = Some("@artist@mastodon.art@fedi".to_string());
+        assert_eq!(
+            artist.serialize_avatar(),
+            unavatar_url("microlink/mastodon.art%2F@artist")
+        );
+
+        let mut artist = Artist::default();
+        let social = Social::new(supported_socials.clone());
+        artist.socials = vec![social.parse_into("artist@misskey.io@fedi").unwrap()];
+        assert_eq!(
+            artist.serialize_avatar(),
+            unavatar_url("microlink/misskey.io%2F@artist")
+        );
+
+        let mut artist = Artist::default();
+        artist.avatar = Some("artist@fedi".to_string());
+        let (_, errors) = artist.resolve_avatars();
+        assert_eq!(errors[0].kind, ErrorKind::MissingInstance);
+    }
+
+    #[test]
+    fn resolve_warnings() {
+        let supported_socials = Rc::from(SupportedSocials::default());
+
+        let mut artist = Artist::default();
+        artist.avatar = Some("foo@twitter@x".to_string());
+        let (_, errors) = artist.resolve_avatars();
+        assert_eq!(
+            errors[0].kind,
+            ErrorKind::InvalidAvatar("foo@twitter@x".to_string())
+        );
+
+        let mut artist = Artist::default();
+        let social = Social::new(supported_socials.clone());
+        artist.avatar = Some("foo@example".to_string());
+        artist.socials = vec![social.parse_into("foo@reddit").unwrap()];
+        let (_, errors) = artist.resolve_avatars();
+        assert_eq!(
+            errors[0].kind,
+            ErrorKind::UnsupportedAvatarCode("example".to_string())
+        );
+
+        let artist = Artist::default();
+        let (_, errors) = artist.resolve_avatars();
+        assert_eq!(errors[0].kind, ErrorKind::NoAvatarSource);
+    }
+
+    #[test]
+    fn serialize_chain() {
+        let providers = AvatarProviders::default();
+
+        // the priority list first, then the socials' order
+        let mut artist = Artist::default();
+        artist.socials = socials(&["foo@twitter", "foo@reddit", "foo@bsky", "foo@github"]);
+        assert_eq!(
+            artist.serialize_avatars_with(&providers),
+            ["bluesky/foo", "github/foo", "twitter/foo", "reddit/foo"].map(unavatar_url)
+        );
+
+        // the artist's candidates before the inferred ones, without duplicates
+        let mut artist = Artist::default();
+        artist.avatar = Some("/foo.png | foo@github | foo@example".to_string());
+        artist.socials = socials(&["foo@github", "foo@twitter"]);
+        let (_, errors) = artist.resolve_avatars();
+        assert_eq!(
+            artist.serialize_avatars_with(&providers),
+            vec![
+                "/avatars/foo.png".to_string(),
+                unavatar_url("github/foo"),
+                unavatar_url("twitter/foo"),
+            ]
+        );
+        assert_eq!(
+            errors[0].kind,
+            ErrorKind::UnsupportedAvatarCode("example".to_string())
+        );
+    }
+
+    #[test]
+    fn serialize_favicon() {
+        let mut artist = Artist::default();
+        artist.socials = socials(&["foo@patreon", "//Foo.example.com:8080/about,Site"]);
+        assert_eq!(
+            artist.serialize_avatar(),
+            unavatar_url("duckduckgo/foo.example.com")
+        );
+        assert!(artist.resolve_avatars().1.is_empty());
+
+        // only when nothing else is found
+        let mut artist = Artist::default();
+        artist.socials = socials(&["//example.com,Site", "foo@github"]);
+        assert_eq!(artist.serialize_avatar(), unavatar_url("github/foo"));
+    }
+
+    #[test]
+    fn serialize_auto() {
+        let supported_socials = Rc::from(SupportedSocials::default());
+
+        let mut artist = Artist::default();
+        let social = Social::new(supported_socials.clone());
+        artist.avatar = Some("_".to_string());
+        artist.socials = vec![social.parse_into("foo@twitter").unwrap()];
+        assert_eq!(artist.serialize_avatar(), unavatar_url("twitter/foo"));
+    }
+}
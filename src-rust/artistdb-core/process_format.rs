@@ -0,0 +1,378 @@
+use std::{collections::HashMap, fmt, rc::Rc};
+
+use crate::{
+    frontend::FrontendSocial,
+    process_artists::{Artist, Artists},
+    process_socials::Social,
+    strip_comment,
+    supported_socials::SupportedSocials,
+    tokenizer::escape,
+};
+
+/// Escaped in a rewritten username: a leading `*` would mark the social as
+/// special, an `@` would end the username
+const USERNAME_ESCAPES: [char; 2] = ['*', '@'];
+
+/// What the formatter may rewrite besides the info lines
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FormatOptions {
+    /// Rewrite social code aliases to their canonical code, `foo@x` to `foo@twitter`
+    pub canonical_codes: bool,
+    /// Rewrite profile URLs of supported platforms, `//x.com/foo` to `foo@twitter`
+    pub match_links: bool,
+    /// Rewrite usernames the way their platform normalizes them, `@Foo@x` to `foo@x`
+    pub fix_usernames: bool,
+}
+
+/// Lines of the source the formatter can't prove are kept in its output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatError {
+    /// Line number in the source, if known, and content of each line
+    pub lost_lines: Vec<(Option<usize>, String)>,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "refusing to format, {} line(s) can't be proven to be kept:",
+            self.lost_lines.len()
+        )?;
+        let gutter = self
+            .lost_lines
+            .iter()
+            .filter_map(|(line, _)| *line)
+            .max()
+            .unwrap_or_default()
+            .to_string()
+            .len();
+        for (line, text) in &self.lost_lines {
+            match line {
+                Some(line) => writeln!(f, "{:>gutter$} | {}", line, text)?,
+                None => writeln!(f, "{:>gutter$} | {}", "?", text)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+/// Username, display name, avatar and aliases as written on an info line
+type InfoFields = (String, Option<String>, Option<String>, Vec<String>);
+
+/// None if the line doesn't parse as an info line
+fn info_fields(line: &str) -> Option<InfoFields> {
+    let mut artist = Artist::default();
+    artist.parse_info(line).ok()?;
+    Some((
+        artist.username,
+        artist.display_name,
+        artist.original_avatar.filter(|avatar| avatar != "_"),
+        artist.alias,
+    ))
+}
+
+/// None if the line doesn't parse as a social line. With `match_links`, a
+/// profile link stands for the `username@code` it's rewritten to
+fn social_fields(
+    supported_socials: &Rc<SupportedSocials>,
+    line: &str,
+    match_links: bool,
+) -> Option<FrontendSocial> {
+    let mut social = Social::new(Rc::clone(supported_socials));
+    social.parse(strip_comment(line).0).ok()?;
+    if match_links {
+        social.match_link();
+    }
+    social.to_frontend().ok()
+}
+
+/// Count each non-blank line
+fn count_lines<'a>(lines: impl Iterator<Item = &'a str>) -> HashMap<&'a str, usize> {
+    let mut counts = HashMap::new();
+    lines
+        .filter(|line| !line.trim().is_empty())
+        .for_each(|line| *counts.entry(line).or_default() += 1);
+    counts
+}
+
+/// Remove one `line` from `counts`, false if there's none left
+fn take_line(counts: &mut HashMap<&str, usize>, line: &str) -> bool {
+    match counts.get_mut(line) {
+        Some(count) if *count > 0 => {
+            *count -= 1;
+            true
+        }
+        _ => false,
+    }
+}
+
+impl Artists {
+    /// A Prettier for the original file. Artists are sorted by username,
+    /// blocks failed to parse are kept verbatim in their original position
+    pub fn to_original(&self) -> String {
+        self.to_original_with(&FormatOptions::default())
+    }
+
+    pub fn to_original_with(&self, options: &FormatOptions) -> String {
+        let artists = self.artists.iter().flat_map(|artist| {
+            let mut result = artist.leading_comments.clone();
+            result.push(match &artist.info_comment {
+                Some(comment) => format!("{} {}", artist.formatted_info_line, comment),
+                None => artist.formatted_info_line.clone(),
+            });
+            result.extend(
+                artist
+                    .raw_social_lines
+                    .iter()
+                    .map(|line| self.format_social_line(line, options)),
+            );
+            std::iter::once(result.join("\n")).chain(artist.trailing_blocks.iter().cloned())
+        });
+
+        self.leading_blocks
+            .iter()
+            .cloned()
+            .chain(artists)
+            .collect::<Vec<String>>()
+            .join("\n\n")
+    }
+
+    /// A social line as written, or with its link or code rewritten
+    fn format_social_line(&self, line: &str, options: &FormatOptions) -> String {
+        let mut social = Social::new(Rc::clone(&self.supported_socials));
+        if social.parse(strip_comment(line).0).is_err() {
+            return line.to_string();
+        }
+        if let (Some(span), true) = (social.link_span.clone(), options.match_links) {
+            if !social.match_link() {
+                return line.to_string();
+            }
+            let (Some(code), Some(username)) = (social.get_code(), social.get_name()) else {
+                return line.to_string();
+            };
+            let variant = social
+                .get_variant()
+                .map(|variant| format!(":{}", variant))
+                .unwrap_or_default();
+            return format!(
+                "{}{}@{}{}{}",
+                &line[..span.start],
+                escape(&username, &USERNAME_ESCAPES),
+                code,
+                variant,
+                &line[span.end..]
+            );
+        }
+        let (Some(code), Some(username), variant) =
+            (social.get_code(), social.get_name(), social.get_variant())
+        else {
+            return line.to_string();
+        };
+        let (Some(username_span), Some(code_span)) = (social.username_span, social.code_span)
+        else {
+            return line.to_string();
+        };
+        let username = match options.fix_usernames && social.normalized {
+            true => escape(&username, &USERNAME_ESCAPES),
+            false => line[username_span.clone()].to_string(),
+        };
+        // The variant is canonical too, lowercased like the code
+        let (code, code_end) = match options.canonical_codes {
+            true => match (variant, social.variant_span) {
+                (Some(variant), Some(variant_span)) => {
+                    (format!("{}:{}", code, variant), variant_span.end)
+                }
+                _ => (code, code_span.end),
+            },
+            false => (line[code_span.clone()].to_string(), code_span.end),
+        };
+        format!(
+            "{}{}@{}{}",
+            &line[..username_span.start],
+            username,
+            code,
+            &line[code_end..]
+        )
+    }
+
+    /// format_with() the default options, only the info lines are rewritten
+    pub fn format(&self) -> Result<String, FormatError> {
+        self.format_with(&FormatOptions::default())
+    }
+
+    /// Same as to_original_with(), but refuse to return anything unless every
+    /// line of the source is proven to be kept:
+    /// - the parser picked up every line, either as an info line, a social
+    ///   line or as part of a block failed to parse
+    /// - every reformatted info line or rewritten social line means the same
+    ///   as the original one
+    /// - formatting the output again changes nothing
+    pub fn format_with(&self, options: &FormatOptions) -> Result<String, FormatError> {
+        let formatted = self.to_original_with(options);
+        let mut lost_lines = vec![];
+        let line_of = |text: &str| {
+            self.source_lines
+                .iter()
+                .find(|(_, line)| line == text)
+                .map(|(line, _)| *line)
+        };
+
+        let mut held = count_lines(
+            self.artists
+                .iter()
+                .flat_map(|artist| {
+                    artist
+                        .leading_comments
+                        .iter()
+                        .chain(std::iter::once(&artist.raw_info_line))
+                        .chain(artist.raw_social_lines.iter())
+                        .map(|line| line.as_str())
+                        .chain(artist.trailing_blocks.iter().flat_map(|raw| raw.lines()))
+                })
+                .chain(self.leading_blocks.iter().flat_map(|raw| raw.lines())),
+        );
+        self.source_lines.iter().for_each(|(line, text)| {
+            if !take_line(&mut held, text) {
+                lost_lines.push((Some(*line), text.clone()));
+            }
+        });
+
+        self.artists.iter().for_each(|artist| {
+            let (raw_info, _) = strip_comment(&artist.raw_info_line);
+            if info_fields(raw_info) != info_fields(&artist.formatted_info_line) {
+                lost_lines.push((Some(artist.info_line), artist.raw_info_line.clone()));
+            }
+        });
+
+        self.artists
+            .iter()
+            .flat_map(|artist| artist.raw_social_lines.iter())
+            .for_each(|line| {
+                let rewritten = self.format_social_line(line, options);
+                if rewritten != *line
+                    && social_fields(&self.supported_socials, line, options.match_links)
+                        != social_fields(&self.supported_socials, &rewritten, false)
+                {
+                    lost_lines.push((line_of(line), line.clone()));
+                }
+            });
+
+        let reformatted =
+            Artists::parse(self.supported_socials.clone(), &formatted).to_original_with(options);
+        let mut kept = count_lines(reformatted.lines());
+        formatted.lines().for_each(|text| {
+            if !text.trim().is_empty() && !take_line(&mut kept, text) {
+                lost_lines.push((line_of(text), text.to_string()));
+            }
+        });
+
+        if !lost_lines.is_empty() {
+            lost_lines.sort();
+            lost_lines.dedup();
+            return Err(FormatError { lost_lines });
+        }
+        Ok(formatted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::supported_socials::SupportedSocials;
+
+    #[test]
+    fn keep_unparseable_blocks() {
+        let raw =
+            ",First\n\nqux,Qux\nqux@twitter\n\n,Broken\nbroken@twitter\n\nfoo,Foo\nfoo@unknown";
+        let artists = Artists::parse(Rc::new(SupportedSocials::default()), raw);
+        // Broken blocks stay after the artist they followed
+        assert_eq!(
+            artists.format().unwrap(),
+            ",First\n\nfoo,Foo,_\nfoo@unknown\n\nqux,Qux,_\nqux@twitter\n\n,Broken\nbroken@twitter"
+        );
+    }
+
+    #[test]
+    fn keep_comments() {
+        let raw = "\u{feff}# header\r\n\r\n\r\n\r\n# why\r\nqux,Qux # note\r\n# qux@twitter\r\nqux@reddit # alt\r\n \r\nfoo,Foo\r\nfoo@twitter";
+        let artists = Artists::parse(Rc::new(SupportedSocials::default()), raw);
+        assert_eq!(artists.errors().count(), 0);
+        assert_eq!(artists.get("qux").unwrap().socials.len(), 1);
+        assert_eq!(
+            artists.format().unwrap(),
+            "# header\n\nfoo,Foo,_\nfoo@twitter\n\n# why\nqux,Qux,_ # note\n# qux@twitter\nqux@reddit # alt"
+        );
+    }
+
+    #[test]
+    fn keep_escapes() {
+        let raw = "foo,\\#1 Foo\\, Bar\\\\,//example.com/#a\nfoo@twitter";
+        let artists = Artists::parse(Rc::new(SupportedSocials::default()), raw);
+        assert_eq!(
+            artists.get("foo").unwrap().display_name.as_deref(),
+            Some("#1 Foo, Bar\\")
+        );
+        assert_eq!(artists.format().unwrap(), raw);
+    }
+
+    #[test]
+    fn canonical_codes() {
+        let raw = "foo,Foo\n*foo@X,Main # old handle\nfoo@bsky\nfoo@twitter\nfoo@DeviantArt:Legacy";
+        let artists = Artists::parse(Rc::new(SupportedSocials::default()), raw);
+        assert_eq!(
+            artists.format().unwrap(),
+            "foo,Foo,_\n*foo@X,Main # old handle\nfoo@bsky\nfoo@twitter\nfoo@DeviantArt:Legacy"
+        );
+        let options = FormatOptions {
+            canonical_codes: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            artists.format_with(&options).unwrap(),
+            "foo,Foo,_\n*foo@twitter,Main # old handle\nfoo@bluesky\nfoo@twitter\nfoo@deviantart:legacy"
+        );
+    }
+
+    #[test]
+    fn match_links() {
+        let raw = "foo,Foo\n*https://x.com/foo?s=20,Main\n//www.pixiv.net/en/users/123\n//pixiv.me/bar\n//example.com,Site\n//x.com/*star";
+        let artists = Artists::parse(Rc::new(SupportedSocials::default()), raw);
+        let options = FormatOptions {
+            match_links: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            artists.format_with(&options).unwrap(),
+            "foo,Foo,_\n*foo@twitter,Main\n123@pixiv\nbar@pixiv:vanity\n//example.com,Site\n\\*star@twitter"
+        );
+    }
+
+    #[test]
+    fn fix_usernames() {
+        let raw = "foo,Foo\n*@Foo/@X,Main\nFoo@twitter\nfoo bar@pixiv";
+        let artists = Artists::parse(Rc::new(SupportedSocials::default()), raw);
+        let options = FormatOptions {
+            fix_usernames: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            artists.format_with(&options).unwrap(),
+            "foo,Foo,_\n*foo@X,Main\nfoo@twitter\nfoo bar@pixiv"
+        );
+    }
+
+    #[test]
+    fn refuse_lossy_info_lines() {
+        let raw = "foo,Foo\\, Bar\nfoo@twitter";
+        let mut artists = Artists::parse(Rc::new(SupportedSocials::default()), raw);
+        artists.artists[0].formatted_info_line = "foo,Foo, Bar".to_string();
+        let err = artists.format().unwrap_err();
+        assert!(err
+            .lost_lines
+            .contains(&(Some(1), "foo,Foo\\, Bar".to_string())));
+    }
+}
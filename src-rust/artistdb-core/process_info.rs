@@ -1,4 +1,8 @@
-use crate::utils::process_artists::Artist;
+use crate::{
+    error::{Error, ErrorKind},
+    process_artists::Artist,
+    tokenizer::{escape, tokenize},
+};
 
 fn cleanup_name(raw: &str) -> String {
     raw.chars()
@@ -12,48 +16,48 @@ fn cleanup_name(raw: &str) -> String {
 
 impl Artist {
     /// Parsing username, display name, avatar and aliases into Artist
-    pub fn parse_info(&mut self, raw: &str) -> Result<(), String> {
-        let components = super::split_components(raw)?;
-
-        if components.is_empty() {
-            return Err("missing fields to parse the info".to_string());
-        }
+    pub fn parse_info(&mut self, raw: &str) -> Result<(), Error> {
+        let components = tokenize(raw)?;
+        self.info_spans = components
+            .iter()
+            .map(|component| component.span.clone())
+            .collect();
 
-        self.username = components
-            .first()
-            .map(|username| cleanup_name(username))
-            .ok_or("missing username")?;
+        self.username = match components.first() {
+            Some(username) if !username.raw().is_empty() => cleanup_name(&username.unescape()),
+            _ => return Err(Error::new(ErrorKind::MissingUsername, 0..raw.len())),
+        };
 
         self.display_name = match components.get(1) {
-            Some(name) if name == &"_".to_string() => None,
-            Some(name) => Some(name.clone()),
+            Some(name) if name.raw() == "_" => None,
+            Some(name) => Some(name.unescape()),
             _ => None,
         };
 
         if let Some(avatar) = components.get(2) {
-            self.original_avatar = Some(avatar.clone());
-            if avatar != "_" {
-                self.avatar = Some(avatar.to_string());
+            self.original_avatar = Some(avatar.unescape());
+            if avatar.raw() != "_" {
+                self.avatar = Some(avatar.unescape());
             }
         }
 
         self.alias = components
             .iter()
             .skip(3)
-            .map(|alias| cleanup_name(alias))
+            .map(|alias| cleanup_name(&alias.unescape()))
             .collect();
 
         Ok(())
     }
 
-    pub fn serialize_info_for_original(&self) -> Result<String, String> {
+    pub fn serialize_info_for_original(&self) -> Result<String, Error> {
         let display_name = match &self.display_name {
-            Some(display_name) => display_name.to_string(),
+            Some(display_name) => escape(display_name, &[]),
             None => "_".to_string(),
         };
 
         let avatar = match &self.original_avatar {
-            Some(avatar) => avatar.to_string(),
+            Some(avatar) => escape(avatar, &[]),
             None => "_".to_string(),
         };
 
@@ -67,11 +71,12 @@ impl Artist {
             &self.username, display_name, avatar, alias
         ))
     }
-    pub fn serialize_info(&self) -> Result<String, String> {
-        let display_name = match &self.display_name {
+
+    /// The display name, falling back to the username
+    pub fn frontend_display_name(&self) -> String {
+        match &self.display_name {
             Some(display_name) => display_name.clone(),
             None => self.username.clone(),
-        };
-        Ok(format!("{},{}", display_name, self.serialize_avatar()?))
+        }
     }
 }
@@ -0,0 +1,616 @@
+use std::{ops::Range, rc::Rc};
+
+use crate::{
+    error::{Error, ErrorKind},
+    frontend::FrontendSocial,
+    supported_socials::SupportedSocials,
+    tokenizer::{tokenize, Token},
+};
+
+#[derive(Debug, Default)]
+pub struct Social {
+    social_code: Option<String>,
+    social_username: Option<String>,
+    /// The URL template variant, when not picked by the username
+    variant: Option<String>,
+    profile_url: Option<String>,
+    description: Option<String>,
+    is_special: bool,
+
+    /// Where the social line is in the source, for errors when serializing
+    pub(crate) span: Range<usize>,
+    /// Where the code is written within the social line
+    pub(crate) code_span: Option<Range<usize>>,
+    /// Where the variant is written, after the code's `:`
+    pub(crate) variant_span: Option<Range<usize>>,
+    /// Where the `//link` is written
+    pub(crate) link_span: Option<Range<usize>>,
+    /// Where the username is written, and if it differs from the normalized one
+    pub(crate) username_span: Option<Range<usize>>,
+    pub(crate) normalized: bool,
+    /// Problems that don't drop the social
+    pub(crate) warnings: Vec<Error>,
+    pub(crate) line: usize,
+
+    supported_socials: Rc<SupportedSocials>,
+}
+
+impl Social {
+    pub fn new(supported_socials: Rc<SupportedSocials>) -> Self {
+        Social {
+            supported_socials,
+            ..Default::default()
+        }
+    }
+
+    /// Parse <*? username@social_code[:variant] || //link>[,<description>] to Social
+    pub fn parse(&mut self, raw: &str) -> Result<(), Error> {
+        // Nothing of a previous parse is kept
+        *self = Social {
+            span: 0..raw.len(),
+            line: 1,
+            ..Social::new(self.supported_socials.clone())
+        };
+        let supported_socials = self.supported_socials.as_ref();
+
+        let tokens = tokenize(raw)?;
+
+        self.description = tokens.get(1).map(|token| token.unescape());
+
+        let first = match tokens.first() {
+            Some(first) => first,
+            None => return Err(Error::new(ErrorKind::MissingSocialCode, 0..raw.len())),
+        };
+
+        let first = match first.strip_prefix('*') {
+            Some(rest) => {
+                self.is_special = true;
+                rest
+            }
+            None => {
+                self.is_special = false;
+                first.clone()
+            }
+        };
+
+        let link = first.unescape();
+        if link.starts_with("//") {
+            self.profile_url = Some(link);
+            self.link_span = Some(first.span);
+            return Ok(());
+        }
+        if let Some(rest) = ["https://", "http://"]
+            .iter()
+            .find_map(|scheme| link.strip_prefix(scheme))
+        {
+            self.profile_url = Some(format!("//{}", rest));
+            self.link_span = Some(first.span);
+            return Ok(());
+        }
+
+        // Using username@social_code, the code is after the last `@`
+        let Some((social_username, social_code)) = first.rsplit_once('@') else {
+            self.social_username = Some(first.unescape());
+            return Err(Error::new(ErrorKind::MissingSocialCode, first.span));
+        };
+        self.social_username = Some(social_username.unescape());
+        self.username_span = Some(social_username.span.clone());
+
+        // The variant is after the `:` of the code
+        let (social_code, variant) = match social_code.split_once(':') {
+            Some((social_code, variant)) => (social_code, Some(variant)),
+            None => (social_code, None),
+        };
+        let code = social_code.unescape().to_ascii_lowercase();
+        if supported_socials.is_avatar_only(&code) {
+            return Err(Error::new(
+                ErrorKind::AvatarOnlyCode(code),
+                social_code.span,
+            ));
+        }
+        if !supported_socials.is_supported(&code) {
+            return Err(Error::new(
+                ErrorKind::UnsupportedSocialCode(code),
+                social_code.span,
+            ));
+        }
+        if let Some(variant) = variant {
+            let name = variant.unescape().to_ascii_lowercase();
+            supported_socials
+                .pick_variant(&code, "", Some(&name))
+                .map_err(|err| Error::new(err.kind, variant.span.clone()))?;
+            self.variant = Some(name);
+            self.variant_span = Some(variant.span);
+        }
+        if supported_socials.is_fediverse(&code) {
+            self.social_username = Some(parse_handle(&social_username)?);
+        }
+        self.code_span = Some(social_code.span);
+        self.social_code = supported_socials
+            .canonical(&code)
+            .map(|code| code.to_string());
+        if !supported_socials.is_fediverse(&code) {
+            self.check_username(social_username.span);
+        }
+        Ok(())
+    }
+
+    /// Turn a pasted profile URL of a supported platform into `username@code`,
+    /// false if the social isn't one. Only the formatter does, with
+    /// `--match-links`
+    pub(crate) fn match_link(&mut self) -> bool {
+        let (Some(link), Some(span)) = (&self.profile_url, self.link_span.clone()) else {
+            return false;
+        };
+        let Some((social_username, social_code, variant)) = self.supported_socials.match_url(link)
+        else {
+            return false;
+        };
+        self.social_username = Some(social_username);
+        self.social_code = Some(social_code);
+        self.variant = variant;
+        self.profile_url = None;
+        self.check_username(span);
+        true
+    }
+
+    /// Normalize the username, with warnings about what `--fix-usernames`
+    /// can fix and what it can't
+    fn check_username(&mut self, span: Range<usize>) {
+        let (Some(code), Some(written)) = (self.social_code.clone(), self.social_username.clone())
+        else {
+            return;
+        };
+        let supported_socials = self.supported_socials.as_ref();
+        // Variants take other kinds of usernames than the platform's rule
+        if let Ok(Some(_)) =
+            supported_socials.pick_variant(&code, &written, self.variant.as_deref())
+        {
+            return;
+        }
+        let username = supported_socials.normalize_username(&code, &written);
+        // Even a change of case makes the published link differ from the source
+        if username != written {
+            self.warnings.push(Error::new(
+                ErrorKind::UnnormalizedUsername(username.clone()),
+                span.clone(),
+            ));
+        }
+        if let Some(problem) = supported_socials.username_problem(&code, &username) {
+            self.warnings
+                .push(Error::new(ErrorKind::InvalidUsername(problem), span));
+        }
+        self.normalized = username != written;
+        self.social_username = Some(username);
+    }
+
+    /// Same as parse(), but return the Social instead of mutating it.
+    /// Use for tests only
+    #[cfg(test)]
+    pub fn parse_into(self, raw: &str) -> Result<Self, Error> {
+        let mut social = self;
+        social.parse(raw)?;
+        Ok(social)
+    }
+
+    /// Serialize the Social to a string [*]<social link>,<social description>
+    pub fn serialize(&self) -> Result<String, Error> {
+        Ok(self.to_frontend()?.encode())
+    }
+
+    /// Resolve the link and the description shown on the frontend
+    pub fn to_frontend(&self) -> Result<FrontendSocial, Error> {
+        let supported_socials = self.supported_socials.as_ref();
+        let located = |kind: ErrorKind| Error {
+            kind,
+            span: self.span.clone(),
+            line: self.line,
+            username: None,
+        };
+
+        let (link, description) =
+            match (&self.profile_url, &self.social_username, &self.social_code) {
+                (Some(profile_url), _, _) if profile_url.starts_with("//") => Ok((
+                    profile_url.to_string(),
+                    self.description
+                        .clone()
+                        .filter(|s| !s.is_empty())
+                        .ok_or_else(|| located(ErrorKind::MissingDescription))?,
+                )),
+                (_, Some(social_username), Some(social_code)) => supported_socials
+                    .get_variant(
+                        social_username,
+                        social_code,
+                        self.variant.as_deref(),
+                        &self.description,
+                    )
+                    .map_err(|err| located(err.kind)),
+                _ => Err(located(ErrorKind::MissingSocialCode)),
+            }?;
+        Ok(FrontendSocial {
+            is_special: self.is_special || supported_socials.is_special(&self.social_code),
+            link,
+            description,
+        })
+    }
+
+    pub fn get_code(&self) -> Option<String> {
+        self.social_code.clone()
+    }
+    pub fn get_name(&self) -> Option<String> {
+        self.social_username.clone()
+    }
+
+    pub fn get_variant(&self) -> Option<String> {
+        self.variant.clone()
+    }
+
+    /// The `//link` of socials that aren't `username@code`
+    pub fn get_link(&self) -> Option<String> {
+        self.profile_url.clone()
+    }
+}
+
+/// `[@]user@instance` into `user@instance`, with the instance lowercased
+fn parse_handle(handle: &Token) -> Result<String, Error> {
+    let handle = handle.strip_prefix('@').unwrap_or(handle.clone());
+    let Some((user, instance)) = handle.split_once('@') else {
+        return Err(Error::new(ErrorKind::MissingInstance, handle.span));
+    };
+    let hostname = instance.unescape().to_ascii_lowercase();
+    if user.raw().is_empty() || !is_hostname(&hostname) {
+        return Err(Error::new(
+            ErrorKind::InvalidInstance(hostname),
+            instance.span,
+        ));
+    }
+    Ok(format!("{}@{}", user.unescape(), hostname))
+}
+
+/// At least two dot separated labels of ASCII letters, digits and inner `-`
+fn is_hostname(hostname: &str) -> bool {
+    let labels = hostname.split('.').collect::<Vec<&str>>();
+    hostname.len() <= 253
+        && labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_valid_normal_cases() {
+        let supported_socials = Rc::from(SupportedSocials::default());
+
+        let mut social = Social::new(supported_socials.clone());
+        social.parse("username@fb").unwrap();
+        assert_eq!(social.social_username, Some("username".to_string()));
+        assert_eq!(social.social_code, Some("facebook".to_string()));
+        assert_eq!(social.code_span, Some(9..11));
+        assert!(!social.is_special);
+
+        let mut social = Social::new(supported_socials.clone());
+        social.parse("//link").unwrap();
+        assert_eq!(social.profile_url, Some("//link".to_string()));
+        assert_eq!(social.social_username, None);
+        assert!(!social.is_special);
+
+        let mut social = Social::new(supported_socials.clone());
+        social.parse("//link,description").unwrap();
+        assert_eq!(social.profile_url, Some("//link".to_string()));
+        assert_eq!(social.description, Some("description".to_string()));
+        assert_eq!(social.social_username, None);
+        assert!(!social.is_special);
+    }
+
+    #[test]
+    fn parse_valid_special_cases() {
+        let supported_socials = Rc::from(SupportedSocials::default());
+
+        let mut social = Social::new(supported_socials.clone());
+        social.parse("*username@linktr.ee").unwrap();
+        assert_eq!(social.social_username, Some("username".to_string()));
+        assert_eq!(social.social_code, Some("linktr.ee".to_string()));
+        assert_eq!(social.profile_url, None);
+        assert!(social.is_special);
+
+        let mut social = Social::new(supported_socials.clone());
+        social.parse("*//link").unwrap();
+        assert_eq!(social.profile_url, Some("//link".to_string()));
+        assert_eq!(social.social_username, None);
+        assert!(social.is_special);
+
+        let mut social = Social::new(supported_socials.clone());
+        social.parse("*//link,description").unwrap();
+        assert_eq!(social.profile_url, Some("//link".to_string()));
+        assert_eq!(social.description, Some("description".to_string()));
+        assert_eq!(social.social_username, None);
+        assert!(social.is_special);
+    }
+
+    #[test]
+    fn parse_escaped() {
+        let supported_socials = Rc::from(SupportedSocials::default());
+
+        let mut social = Social::new(supported_socials.clone());
+        social
+            .parse("\\*star\\\\@twitter,Commissions\\, open")
+            .unwrap();
+        assert_eq!(social.social_username, Some("*star\\".to_string()));
+        assert_eq!(social.description, Some("Commissions, open".to_string()));
+        assert!(!social.is_special);
+    }
+
+    #[test]
+    fn parse_invalid_cases() {
+        let supported_socials = Rc::from(SupportedSocials::default());
+        let parse = |raw: &str| Social::new(supported_socials.clone()).parse(raw);
+
+        assert!(parse("username").is_err());
+        assert!(parse("username@invalid_code").is_err());
+        assert!(parse("@invalid_code").is_err());
+        assert!(parse("username\\@twitter").is_err());
+
+        let err = parse("username@google").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::AvatarOnlyCode("google".to_string()));
+        assert_eq!(err.span, 9..15);
+
+        let err = parse("*username@invalid_code,desc").unwrap_err();
+        assert_eq!(
+            err.kind,
+            ErrorKind::UnsupportedSocialCode("invalid_code".to_string())
+        );
+        assert_eq!(err.span, 10..22);
+
+        let err = parse(" username ,desc").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::MissingSocialCode);
+        assert_eq!(err.span, 1..9);
+    }
+
+    #[test]
+    fn parse_urls() {
+        let supported_socials = Rc::from(SupportedSocials::default());
+
+        // Links are kept as written unless matched
+        let mut social = Social::new(supported_socials.clone());
+        social.parse("*https://x.com/foo?s=20,Main").unwrap();
+        assert_eq!(social.get_code(), None);
+        assert_eq!(social.link_span, Some(1..23));
+        assert_eq!(social.serialize().unwrap(), "*//x.com/foo?s=20,Main");
+
+        assert!(social.match_link());
+        assert_eq!(social.get_code(), Some("twitter".to_string()));
+        assert_eq!(social.get_name(), Some("foo".to_string()));
+        assert_eq!(social.serialize().unwrap(), "*//twitter.com/foo,𝕏 | Main");
+
+        let mut social = Social::new(supported_socials.clone());
+        social.parse("https://example.com/foo,Site").unwrap();
+        assert_eq!(social.profile_url, Some("//example.com/foo".to_string()));
+        assert!(!social.match_link());
+        assert_eq!(social.profile_url, Some("//example.com/foo".to_string()));
+    }
+
+    #[test]
+    fn parse_again() {
+        let mut social = Social::new(Rc::from(SupportedSocials::default()));
+        social.parse("*foo@youtube:custom,Main").unwrap();
+        social.parse("//example.com").unwrap();
+        assert_eq!(social.profile_url, Some("//example.com".to_string()));
+        assert_eq!(social.social_code, None);
+        assert_eq!(social.social_username, None);
+        assert_eq!(social.variant, None);
+        assert_eq!(social.description, None);
+        assert!(!social.is_special);
+
+        social.parse("foo@twitter").unwrap();
+        assert_eq!(social.profile_url, None);
+        assert_eq!(social.get_code(), Some("twitter".to_string()));
+    }
+
+    #[test]
+    fn parse_usernames() {
+        let supported_socials = Rc::from(SupportedSocials::default());
+
+        let mut social = Social::new(supported_socials.clone());
+        social.parse("@Foo/@x").unwrap();
+        assert_eq!(social.get_name(), Some("foo".to_string()));
+        assert!(social.normalized);
+        assert_eq!(social.username_span, Some(0..5));
+        assert_eq!(
+            social.warnings,
+            vec![Error::new(
+                ErrorKind::UnnormalizedUsername("foo".to_string()),
+                0..5
+            )]
+        );
+
+        let mut social = Social::new(supported_socials.clone());
+        // Only a change of case, still published differently
+        social.parse("Foo@twitter").unwrap();
+        assert_eq!(social.get_name(), Some("foo".to_string()));
+        assert_eq!(
+            social.warnings,
+            vec![Error::new(
+                ErrorKind::UnnormalizedUsername("foo".to_string()),
+                0..3
+            )]
+        );
+
+        social.parse("Foo@fa").unwrap();
+        assert_eq!(social.get_name(), Some("Foo".to_string()));
+        assert_eq!(social.warnings, vec![]);
+
+        let mut social = Social::new(supported_socials.clone());
+        social.parse("foo bar@pixiv,Art").unwrap();
+        assert_eq!(
+            social
+                .warnings
+                .iter()
+                .map(|err| &err.kind)
+                .collect::<Vec<_>>(),
+            vec![&ErrorKind::InvalidUsername(
+                "`foo bar` contains whitespace".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn parse_fediverse() {
+        let supported_socials = Rc::from(SupportedSocials::default());
+        let parse = |raw: &str| Social::new(supported_socials.clone()).parse(raw);
+
+        let mut social = Social::new(supported_socials.clone());
+        social.parse("@artist@Mastodon.Art@fedi").unwrap();
+        assert_eq!(social.get_name(), Some("artist@mastodon.art".to_string()));
+        assert_eq!(
+            social.serialize().unwrap(),
+            "//mastodon.art/@artist,Fediverse"
+        );
+
+        let err = parse("artist@fedi").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::MissingInstance);
+        assert_eq!(err.span, 0..6);
+
+        let err = parse("artist@not_a.host@fedi").unwrap_err();
+        assert_eq!(
+            err.kind,
+            ErrorKind::InvalidInstance("not_a.host".to_string())
+        );
+        assert_eq!(err.span, 7..17);
+
+        assert!(parse("artist@localhost@fedi").is_err());
+        assert!(parse("@mastodon.art@fedi").is_err());
+    }
+
+    #[test]
+    fn parse_variants() {
+        let supported_socials = Rc::from(SupportedSocials::default());
+        let parse = |raw: &str| Social::new(supported_socials.clone()).parse(raw);
+
+        let mut social = Social::new(supported_socials.clone());
+        social.parse("foo@YouTube:Custom").unwrap();
+        assert_eq!(social.get_variant(), Some("custom".to_string()));
+        assert_eq!(social.code_span, Some(4..11));
+        assert_eq!(social.serialize().unwrap(), "//youtube.com/c/foo,YouTube");
+
+        let mut social = Social::new(supported_socials.clone());
+        social.parse("UCaaaaaaaaaaaaaaaaaaaaaa@youtube").unwrap();
+        assert_eq!(social.get_variant(), None);
+        assert!(social.warnings.is_empty());
+        assert_eq!(
+            social.serialize().unwrap(),
+            "//youtube.com/channel/UCaaaaaaaaaaaaaaaaaaaaaa,YouTube"
+        );
+
+        let mut social = Social::new(supported_socials.clone());
+        social.parse("https://pixiv.me/foo").unwrap();
+        assert!(social.match_link());
+        assert_eq!(social.get_variant(), Some("vanity".to_string()));
+        assert!(social.warnings.is_empty());
+
+        let err = parse("foo@youtube:nope").unwrap_err();
+        assert_eq!(
+            err.kind,
+            ErrorKind::UnknownVariant("youtube".to_string(), "nope".to_string())
+        );
+        assert_eq!(err.span, 12..16);
+    }
+
+    #[test]
+    fn serialize_valid() {
+        let supported = Rc::new(SupportedSocials::default());
+
+        assert_eq!(
+            Social {
+                social_username: Some("username".to_string()),
+                social_code: Some("fb".to_string()),
+                supported_socials: supported.clone(),
+                ..Default::default()
+            }
+            .serialize()
+            .unwrap(),
+            "//fb.com/username,Facebook"
+        );
+        assert_eq!(
+            Social {
+                social_username: Some("username".to_string()),
+                social_code: Some("x".to_string()),
+                supported_socials: supported.clone(),
+                ..Default::default()
+            }
+            .serialize()
+            .unwrap(),
+            "//twitter.com/username,𝕏"
+        );
+        assert_eq!(
+            Social {
+                social_username: Some("username".to_string()),
+                social_code: Some("instagram".to_string()),
+                description: Some("hi".to_string()),
+                supported_socials: supported.clone(),
+                ..Default::default()
+            }
+            .serialize()
+            .unwrap(),
+            "//instagram.com/username,Instagram | hi"
+        );
+
+        assert_eq!(
+            Social {
+                profile_url: Some("//link".to_string()),
+                description: Some("hi".to_string()),
+                supported_socials: supported.clone(),
+                ..Default::default()
+            }
+            .serialize()
+            .unwrap(),
+            "//link,hi"
+        );
+    }
+
+    #[test]
+    fn serialize_invalid() {
+        let supported = Rc::new(SupportedSocials::default());
+
+        assert!(Social::default().serialize().is_err());
+        assert!(Social {
+            social_username: Some("username".to_string()),
+            supported_socials: supported.clone(),
+            ..Default::default()
+        }
+        .serialize()
+        .is_err());
+
+        assert!(Social {
+            social_code: Some("x".to_string()),
+            supported_socials: supported.clone(),
+            ..Default::default()
+        }
+        .serialize()
+        .is_err());
+
+        assert!(Social {
+            profile_url: Some("//link".to_string()),
+            description: Some("".to_string()),
+            supported_socials: supported.clone(),
+            ..Default::default()
+        }
+        .serialize()
+        .is_err(),);
+
+        assert!(Social {
+            profile_url: Some("//link".to_string()),
+            supported_socials: supported.clone(),
+            ..Default::default()
+        }
+        .serialize()
+        .is_err());
+    }
+}
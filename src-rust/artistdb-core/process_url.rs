@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::supported_socials::SupportedSocials;
+
+/// Encoded in values substituted into a path, so they stay one segment
+pub(crate) const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'\\')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/// `[[platform.variants]]` of a registry file, another URL template of the
+/// same platform
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Variant {
+    /// Picked with `username@code:name`
+    pub name: String,
+    pub url: String,
+    /// Picked without a name when the username matches, named groups fill the
+    /// placeholders of the same name
+    #[serde(rename = "match")]
+    pub pattern: Option<String>,
+}
+
+/// A Variant with its regex compiled
+#[derive(Debug)]
+pub(crate) struct CompiledVariant {
+    pub(crate) name: String,
+    pub(crate) url: String,
+    pub(crate) pattern: Option<Regex>,
+}
+
+impl Variant {
+    pub(crate) fn compile(&self) -> Result<CompiledVariant, regex::Error> {
+        let pattern = match &self.pattern {
+            Some(pattern) => Some(Regex::new(pattern)?),
+            None => None,
+        };
+        Ok(CompiledVariant {
+            name: self.name.clone(),
+            url: self.url.clone(),
+            pattern,
+        })
+    }
+}
+
+impl CompiledVariant {
+    /// Named groups of the pattern the username matches
+    pub(crate) fn captures(&self, username: &str) -> Vec<(String, String)> {
+        let Some(captures) = self
+            .pattern
+            .as_ref()
+            .and_then(|pattern| pattern.captures(username))
+        else {
+            return vec![];
+        };
+        self.pattern
+            .iter()
+            .flat_map(|pattern| pattern.capture_names())
+            .flatten()
+            .filter_map(|name| Some((name.to_string(), captures.name(name)?.as_str().to_string())))
+            .collect()
+    }
+}
+
+/// Names of the `<name>` placeholders in a template, `@` for `<@>`
+pub(crate) fn placeholders(template: &str) -> Vec<&str> {
+    template
+        .split('<')
+        .skip(1)
+        .filter_map(|rest| rest.split_once('>').map(|(name, _)| name))
+        .collect()
+}
+
+/// Substitute every `<name>` placeholder with its value, percent-encoded
+/// unless it's part of the host. Unknown placeholders are kept as is
+pub(crate) fn fill_template(template: &str, values: &HashMap<&str, String>) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('<') {
+        let Some(end) = rest[start..].find('>').map(|end| start + end) else {
+            break;
+        };
+        filled.push_str(&rest[..start]);
+        match values.get(&rest[start + 1..end]) {
+            Some(value) if filled.contains('/') => {
+                filled.extend(utf8_percent_encode(value, SEGMENT))
+            }
+            Some(value) => filled.push_str(value),
+            None => filled.push_str(&rest[start..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    filled.push_str(rest);
+    filled
+}
+
+/// Host and path of a URL or a template, lowercased host without `www.`,
+/// without the scheme, the query, the fragment and the trailing `/`
+fn split_url(url: &str) -> (String, &str) {
+    let url = url.split(['?', '#']).next().unwrap_or_default();
+    let url = url.trim_end_matches('/');
+    let (host, path) = url.split_once('/').unwrap_or((url, ""));
+    let host = host.to_ascii_lowercase();
+    match host.strip_prefix("www.") {
+        Some(host) => (host.to_string(), path),
+        None => (host, path),
+    }
+}
+
+/// The URL without its `https://`, `http://` or `//`, None if it isn't one
+fn strip_scheme(url: &str) -> Option<&str> {
+    ["https://", "http://", "//"].iter().find_map(|scheme| {
+        url.get(..scheme.len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case(scheme))
+            .map(|_| &url[scheme.len()..])
+    })
+}
+
+/// What `<@>` stands for in `pattern`, if `value` matches it. The username
+/// can't span more than one label or path segment
+fn match_placeholder<'a>(pattern: &str, value: &'a str, separator: char) -> Option<&'a str> {
+    let (prefix, suffix) = pattern.split_once("<@>")?;
+    let username = value.strip_prefix(prefix)?.strip_suffix(suffix)?;
+    match username.is_empty() || username.contains(separator) {
+        true => None,
+        false => Some(username),
+    }
+}
+
+/// What `<@>` stands for in `template`, if `host` and `path` are a profile URL
+/// of it. Templates with other placeholders never match
+fn match_template<'a>(
+    template: &str,
+    hosts: &[String],
+    host: &'a str,
+    path: &'a str,
+) -> Option<&'a str> {
+    if template.matches('<').count() != 1 {
+        return None;
+    }
+    let (template_host, template_path) = split_url(template);
+    if template_host.contains("<@>") {
+        return match template_path == path {
+            true => match_placeholder(&template_host, host, '.'),
+            false => None,
+        };
+    }
+    let known_host = template_host == host || hosts.iter().any(|known| known == host);
+    match known_host {
+        true => match_placeholder(template_path, path, '/'),
+        false => None,
+    }
+}
+
+/// Reverse of the URL templates, turning pasted profile links into socials
+impl SupportedSocials {
+    /// `(username, code, variant)` for a `https://`, `http://` or `//` profile
+    /// URL of a supported platform. The most specific template wins, the
+    /// variant is only named when the username alone wouldn't pick it
+    pub fn match_url(&self, url: &str) -> Option<(String, String, Option<String>)> {
+        let (host, path) = split_url(strip_scheme(url)?);
+
+        let mut matches = self
+            .templates()
+            .filter_map(|(code, variant, template, hosts)| {
+                let username = match_template(template, hosts, &host, path)?;
+                if self.is_reserved(code, username) {
+                    return None;
+                }
+                Some((template.len(), code, variant, username))
+            })
+            .collect::<Vec<(usize, &str, Option<&str>, &str)>>();
+        matches.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(b.1)).then(a.2.cmp(&b.2)));
+
+        let (_, code, variant, username) = matches.first()?;
+        let username = percent_decode_str(username).decode_utf8_lossy().to_string();
+        let picked = self
+            .pick_variant(code, &username, None)
+            .ok()
+            .flatten()
+            .map(|picked| picked.name.as_str());
+        let variant = variant.filter(|variant| picked != Some(*variant));
+        Some((
+            username,
+            code.to_string(),
+            variant.map(|variant| variant.to_string()),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_templates() {
+        let values = HashMap::from([
+            ("@", "a b/あ".to_string()),
+            ("instance", "mastodon.art".to_string()),
+            ("user", "foo".to_string()),
+        ]);
+        assert_eq!(
+            fill_template("example.com/<@>", &values),
+            "example.com/a%20b%2F%E3%81%82"
+        );
+        assert_eq!(
+            fill_template("<instance>/@<user>/<unknown>", &values),
+            "mastodon.art/@foo/<unknown>"
+        );
+        assert_eq!(placeholders("<instance>/@<user>"), vec!["instance", "user"]);
+    }
+
+    #[test]
+    fn match_urls() {
+        let supported_socials = SupportedSocials::default();
+        let matched = |url: &str| supported_socials.match_url(url);
+        let social =
+            |username: &str, code: &str| Some((username.to_string(), code.to_string(), None));
+
+        assert_eq!(
+            matched("https://www.pixiv.net/en/users/12345"),
+            social("12345", "pixiv")
+        );
+        assert_eq!(matched("https://x.com/foo"), social("foo", "twitter"));
+        assert_eq!(matched("HTTP://Twitter.com/foo/"), social("foo", "twitter"));
+        assert_eq!(
+            matched("https://www.instagram.com/foo.bar/?igsh=abc"),
+            social("foo.bar", "instagram")
+        );
+        assert_eq!(
+            matched("//www.furaffinity.net/user/foo"),
+            social("foo", "fa")
+        );
+        assert_eq!(
+            matched("https://foo.carrd.co/#about"),
+            social("foo", "carrd.co")
+        );
+        assert_eq!(
+            matched("https://youtube.com/@foo"),
+            social("foo", "youtube")
+        );
+
+        assert_eq!(
+            matched("https://www.youtube.com/channel/UCaaaaaaaaaaaaaaaaaaaaaa"),
+            social("UCaaaaaaaaaaaaaaaaaaaaaa", "youtube")
+        );
+        assert_eq!(
+            matched("https://pixiv.me/%E3%81%82"),
+            Some((
+                "あ".to_string(),
+                "pixiv".to_string(),
+                Some("vanity".to_string())
+            ))
+        );
+
+        assert_eq!(matched("https://x.com/foo/status/1"), None);
+        assert_eq!(matched("https://x.com/home"), None);
+        assert_eq!(matched("https://twitter.com/Explore"), None);
+        assert_eq!(matched("https://www.instagram.com/explore/"), None);
+        assert_eq!(matched("https://example.com/foo"), None);
+        assert_eq!(matched("https://x.com/"), None);
+        assert_eq!(matched("https://mastodon.art/@foo"), None);
+        assert_eq!(matched("x.com/foo"), None);
+    }
+}
@@ -0,0 +1,156 @@
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::supported_socials::SupportedSocials;
+
+/// `[platform.username]` of a registry file, how usernames look on the platform
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UsernameRule {
+    /// Allowed characters, as the inside of a regex `[...]` class
+    pub charset: Option<String>,
+    /// Length in characters
+    pub min: Option<usize>,
+    pub max: Option<usize>,
+    /// A regex the whole username has to match
+    pub pattern: Option<String>,
+    /// Applied in order, before checking the rest
+    #[serde(default)]
+    pub normalize: Vec<Normalize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Normalize {
+    /// `@foo` to `foo`
+    StripAt,
+    /// `foo/` to `foo`
+    TrimSlash,
+    /// `Foo` to `foo`, on case-insensitive platforms
+    Lowercase,
+}
+
+/// A UsernameRule with its regexes compiled
+#[derive(Debug)]
+pub(crate) struct CompiledRule {
+    charset: Option<(String, Regex)>,
+    min: Option<usize>,
+    max: Option<usize>,
+    pattern: Option<Regex>,
+    normalize: Vec<Normalize>,
+}
+
+impl UsernameRule {
+    pub(crate) fn compile(&self) -> Result<CompiledRule, regex::Error> {
+        let charset = match &self.charset {
+            Some(charset) => Some((charset.clone(), Regex::new(&format!("^[{}]*$", charset))?)),
+            None => None,
+        };
+        let pattern = match &self.pattern {
+            Some(pattern) => Some(Regex::new(pattern)?),
+            None => None,
+        };
+        Ok(CompiledRule {
+            charset,
+            min: self.min,
+            max: self.max,
+            pattern,
+            normalize: self.normalize.clone(),
+        })
+    }
+}
+
+impl SupportedSocials {
+    /// The username after the platform's normalization steps
+    pub fn normalize_username(&self, code: &str, username: &str) -> String {
+        let Some(rule) = self.username_rule(code) else {
+            return username.to_string();
+        };
+        rule.normalize
+            .iter()
+            .fold(username.to_string(), |username, step| match step {
+                Normalize::StripAt => username.trim_start_matches('@').to_string(),
+                Normalize::TrimSlash => username.trim_end_matches('/').to_string(),
+                Normalize::Lowercase => username.to_lowercase(),
+            })
+    }
+
+    /// Why the username can't be one on the platform, if it can't
+    pub fn username_problem(&self, code: &str, username: &str) -> Option<String> {
+        if username.contains(char::is_whitespace) {
+            return Some(format!("`{}` contains whitespace", username));
+        }
+        let rule = self.username_rule(code)?;
+        let length = username.chars().count();
+        if let Some((charset, regex)) = &rule.charset {
+            if !regex.is_match(username) {
+                return Some(format!(
+                    "`{}` has characters outside of `{}`",
+                    username, charset
+                ));
+            }
+        }
+        if let Some(min) = rule.min.filter(|min| length < *min) {
+            return Some(format!("`{}` is shorter than {} characters", username, min));
+        }
+        if let Some(max) = rule.max.filter(|max| length > *max) {
+            return Some(format!("`{}` is longer than {} characters", username, max));
+        }
+        match &rule.pattern {
+            Some(pattern) if !pattern.is_match(username) => Some(format!(
+                "`{}` doesn't match `{}`",
+                username,
+                pattern.as_str()
+            )),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize() {
+        let supported_socials = SupportedSocials::default();
+
+        assert_eq!(supported_socials.normalize_username("x", "@Foo/"), "foo");
+        assert_eq!(
+            supported_socials.normalize_username("instagram", "foo/"),
+            "foo"
+        );
+        assert_eq!(supported_socials.normalize_username("pixiv", "123/"), "123");
+        assert_eq!(supported_socials.normalize_username("fa", "@Foo"), "@Foo");
+    }
+
+    #[test]
+    fn problems() {
+        let supported_socials = SupportedSocials::default();
+        let problem =
+            |code: &str, username: &str| supported_socials.username_problem(code, username);
+
+        assert_eq!(problem("twitter", "foo_bar"), None);
+        assert_eq!(problem("pixiv", "12345"), None);
+        assert_eq!(
+            problem("fa", "foo bar"),
+            Some("`foo bar` contains whitespace".to_string())
+        );
+        assert_eq!(
+            problem("pixiv", "Foo"),
+            Some("`Foo` doesn't match `^[0-9]+$`".to_string())
+        );
+        assert_eq!(
+            problem("twitter", "foo.bar"),
+            Some("`foo.bar` has characters outside of `A-Za-z0-9_`".to_string())
+        );
+        assert_eq!(
+            problem("twitter", "a_very_long_handle"),
+            Some("`a_very_long_handle` is longer than 15 characters".to_string())
+        );
+        assert_eq!(
+            problem("twitch", "foo"),
+            Some("`foo` is shorter than 4 characters".to_string())
+        );
+    }
+}
@@ -0,0 +1,175 @@
+//! The index of every artist the landing page searches as you type, one file:
+//!
+//! ```text
+//! artistdb-index <version>
+//! <username>,<display name>[,<alias>...][,<handle>@<code>...]
+//! ...
+//! ```
+//!
+//! Sorted by username, so it can be searched by prefix. Aliases never contain
+//! an `@`, handles always do. Fields are escaped like in [`crate::frontend`].
+
+use crate::{
+    frontend::{decode_fields, encode_field, DecodeError},
+    process_artists::Artist,
+};
+
+pub const INDEX_VERSION: u32 = 1;
+const HEADER_PREFIX: &str = "artistdb-index ";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub username: String,
+    pub display_name: String,
+    pub aliases: Vec<String>,
+    /// `username@code` of every platform the artist is on
+    pub handles: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchIndex {
+    pub entries: Vec<IndexEntry>,
+}
+
+impl IndexEntry {
+    pub fn from_artist(artist: &Artist) -> IndexEntry {
+        let mut handles: Vec<String> = vec![];
+        artist
+            .socials
+            .iter()
+            .filter_map(|social| Some(format!("{}@{}", social.get_name()?, social.get_code()?)))
+            .for_each(|handle| {
+                if !handles.contains(&handle) {
+                    handles.push(handle);
+                }
+            });
+        IndexEntry {
+            username: artist.username.clone(),
+            display_name: artist.frontend_display_name(),
+            aliases: artist.alias.clone(),
+            handles,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        [&self.username, &self.display_name]
+            .into_iter()
+            .chain(&self.aliases)
+            .chain(&self.handles)
+            .map(|field| encode_field(field))
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+}
+
+impl SearchIndex {
+    /// The entries of `artists`, sorted by username
+    pub fn new<'a>(artists: impl IntoIterator<Item = &'a Artist>) -> SearchIndex {
+        let mut entries = artists
+            .into_iter()
+            .map(IndexEntry::from_artist)
+            .collect::<Vec<IndexEntry>>();
+        entries.sort_by(|a, b| a.username.cmp(&b.username));
+        SearchIndex { entries }
+    }
+
+    pub fn encode(&self) -> String {
+        let mut lines = vec![format!("{}{}", HEADER_PREFIX, INDEX_VERSION)];
+        lines.extend(self.entries.iter().map(|entry| entry.encode()));
+        lines.join("\n")
+    }
+
+    pub fn decode(raw: &str) -> Result<SearchIndex, DecodeError> {
+        let mut lines = raw.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+        let version = lines
+            .next()
+            .and_then(|(_, header)| header.strip_prefix(HEADER_PREFIX))
+            .ok_or(DecodeError::MissingHeader)?;
+        if version != INDEX_VERSION.to_string() {
+            return Err(DecodeError::UnsupportedVersion(version.to_string()));
+        }
+
+        let entries = lines
+            .map(|(i, line)| {
+                let fields = decode_fields(line).ok_or(DecodeError::InvalidEscape(i))?;
+                if fields.len() < 2 {
+                    return Err(DecodeError::InvalidFieldCount(i));
+                }
+                let mut fields = fields.into_iter();
+                let username = fields.next().unwrap_or_default();
+                let display_name = fields.next().unwrap_or_default();
+                let (handles, aliases) = fields.partition(|field| field.contains('@'));
+                Ok(IndexEntry {
+                    username,
+                    display_name,
+                    aliases,
+                    handles,
+                })
+            })
+            .collect::<Result<Vec<IndexEntry>, DecodeError>>()?;
+
+        Ok(SearchIndex { entries })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{process_artists::Artists, supported_socials::SupportedSocials};
+
+    #[test]
+    fn index_artists() {
+        let raw = "foo,Foo\\, the one,_,fu,foofoo\nfoo@twitter\n*foo@twitter\nfoo@mastodon.social@fedi\n//example.com,Shop\n\nbar\nbar@github";
+        let artists = Artists::parse(Rc::new(SupportedSocials::default()), raw);
+        let index = SearchIndex::new(&artists);
+
+        assert_eq!(
+            index.entries,
+            vec![
+                IndexEntry {
+                    username: "bar".to_string(),
+                    display_name: "bar".to_string(),
+                    aliases: vec![],
+                    handles: vec!["bar@github".to_string()],
+                },
+                IndexEntry {
+                    username: "foo".to_string(),
+                    display_name: "Foo, the one".to_string(),
+                    aliases: vec!["fu".to_string(), "foofoo".to_string()],
+                    handles: vec![
+                        "foo@twitter".to_string(),
+                        "foo@mastodon.social@fedi".to_string()
+                    ],
+                },
+            ]
+        );
+        assert_eq!(
+            index.encode(),
+            "artistdb-index 1\nbar,bar,bar@github\nfoo,Foo\\, the one,fu,foofoo,foo@twitter,foo@mastodon.social@fedi"
+        );
+        assert_eq!(SearchIndex::decode(&index.encode()).unwrap(), index);
+    }
+
+    #[test]
+    fn decode_invalid_index() {
+        assert_eq!(
+            SearchIndex::decode("artistdb 4\nfoo,Foo").unwrap_err(),
+            DecodeError::MissingHeader
+        );
+        assert_eq!(
+            SearchIndex::decode("artistdb-index 0").unwrap_err(),
+            DecodeError::UnsupportedVersion("0".to_string())
+        );
+        assert_eq!(
+            SearchIndex::decode("artistdb-index 1\nfoo").unwrap_err(),
+            DecodeError::InvalidFieldCount(2)
+        );
+        assert_eq!(
+            SearchIndex::decode("artistdb-index 1\nfoo,Foo\\x").unwrap_err(),
+            DecodeError::InvalidEscape(2)
+        );
+    }
+}
@@ -0,0 +1,1013 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, io,
+    path::Path,
+};
+
+use serde::Deserialize;
+
+use crate::{
+    error::{Error, ErrorKind},
+    process_url::{fill_template, placeholders, CompiledVariant, Variant},
+    process_username::{CompiledRule, UsernameRule},
+};
+
+type SocialCode = String;
+
+/// The registry bundled into the crate, see `socials.toml` for the fields
+const BUNDLED: &str = include_str!("socials.toml");
+
+#[derive(Debug)]
+pub struct SupportedSocials {
+    /// Every accepted code or alias, to its canonical code
+    codes: HashMap<SocialCode, SocialCode>,
+    /// Platforms with profile pages, usable as socials
+    profiles: HashMap<SocialCode, (Description, ProfileUrl)>,
+    /// Codes unavatar can fetch an avatar from
+    avatars: HashSet<SocialCode>,
+    specials: HashSet<SocialCode>,
+    fediverse: HashSet<SocialCode>,
+    hosts: HashMap<SocialCode, Vec<String>>,
+    /// Lowercased paths that aren't profiles, by platform
+    reserved: HashMap<SocialCode, HashSet<String>>,
+    usernames: HashMap<SocialCode, CompiledRule>,
+    variants: HashMap<SocialCode, Vec<CompiledVariant>>,
+    /// Codes inferred avatars are picked from first, in order
+    avatar_priority: Vec<SocialCode>,
+    /// Avatar provider taking domains, for artists with only `//` links
+    favicon_provider: Option<SocialCode>,
+    /// Which of those links' domains it's used for, see favicon()
+    favicon_domains: Vec<String>,
+}
+
+/// One `[[platform]]` entry of a registry file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Platform {
+    pub code: SocialCode,
+    #[serde(default)]
+    pub aliases: Vec<SocialCode>,
+    pub name: Description,
+    /// None for avatar providers without profile pages
+    #[serde(default)]
+    pub url: Option<ProfileUrl>,
+    /// Other URL templates, picked by name or by the username
+    #[serde(default)]
+    pub variants: Vec<Variant>,
+    /// Other hostnames serving the same profile URLs, for matching pasted links
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    /// Paths that aren't profiles, never read as a username from a link
+    #[serde(default)]
+    pub reserved: Vec<String>,
+    /// How usernames look on the platform
+    #[serde(default)]
+    pub username: Option<UsernameRule>,
+    #[serde(default)]
+    pub special: bool,
+    #[serde(default)]
+    pub avatar: bool,
+    #[serde(default)]
+    pub fediverse: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Registry {
+    /// Replaces the current priority list unless empty
+    #[serde(default)]
+    avatar_priority: Vec<SocialCode>,
+    /// Replaces the current provider and domains if set
+    favicon_provider: Option<SocialCode>,
+    favicon_domains: Option<Vec<String>>,
+    #[serde(default)]
+    platform: Vec<Platform>,
+}
+
+/// A problem within one registry file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryIssue {
+    /// The code or alias is defined by more than one entry
+    DuplicateCode(SocialCode),
+    /// The code is defined both with and without avatar support
+    ConflictingAvatar(SocialCode),
+    /// A profile platform with an empty or missing URL template
+    EmptyTemplate(SocialCode),
+    /// The username charset or pattern isn't a valid regex
+    InvalidUsernameRule(SocialCode),
+    /// A variant with an invalid regex, or a name used twice
+    InvalidVariant(SocialCode),
+    /// A template placeholder nothing can fill
+    UnknownPlaceholder(SocialCode),
+    /// A code in `avatar_priority` that no avatar can be inferred from
+    UnknownAvatarPriority(SocialCode),
+    /// A `favicon_provider` that isn't an avatar provider
+    UnknownFaviconProvider(SocialCode),
+    /// The code or alias already belongs to another platform of the registry
+    /// being extended, the first code is the taken one, the second its owner
+    TakenCode(SocialCode, SocialCode),
+}
+
+impl fmt::Display for RegistryIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryIssue::DuplicateCode(code) => write!(f, "duplicate code `{}`", code),
+            RegistryIssue::ConflictingAvatar(code) => write!(
+                f,
+                "code `{}` is defined both with and without avatar support",
+                code
+            ),
+            RegistryIssue::EmptyTemplate(code) => write!(f, "empty URL template for `{}`", code),
+            RegistryIssue::InvalidUsernameRule(code) => {
+                write!(f, "invalid username charset or pattern for `{}`", code)
+            }
+            RegistryIssue::InvalidVariant(code) => write!(f, "invalid variant for `{}`", code),
+            RegistryIssue::UnknownPlaceholder(code) => {
+                write!(f, "unknown placeholder in a template for `{}`", code)
+            }
+            RegistryIssue::UnknownAvatarPriority(code) => {
+                write!(f, "`{}` in avatar_priority isn't an avatar source", code)
+            }
+            RegistryIssue::UnknownFaviconProvider(code) => {
+                write!(f, "favicon_provider `{}` isn't an avatar provider", code)
+            }
+            RegistryIssue::TakenCode(code, owner) => {
+                write!(f, "code `{}` already belongs to `{}`", code, owner)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RegistryError {
+    Toml(toml::de::Error),
+    Invalid(Vec<RegistryIssue>),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::Toml(err) => write!(f, "{}", err),
+            RegistryError::Invalid(issues) => {
+                write!(f, "{} issue(s) in the registry", issues.len())?;
+                issues
+                    .iter()
+                    .try_for_each(|issue| write!(f, "\n  {}", issue))
+            }
+        }
+    }
+}
+
+/// Checks one registry file on its own, overriding the bundled entries is fine
+pub fn validate(platforms: &[Platform]) -> Vec<RegistryIssue> {
+    let mut issues = vec![];
+    let mut seen: HashMap<&str, bool> = HashMap::new();
+    for platform in platforms {
+        match &platform.url {
+            Some(url) if !url.trim().is_empty() => {}
+            None if platform.avatar => {}
+            _ => issues.push(RegistryIssue::EmptyTemplate(platform.code.clone())),
+        }
+        if let Some(Err(_)) = platform.username.as_ref().map(|rule| rule.compile()) {
+            issues.push(RegistryIssue::InvalidUsernameRule(platform.code.clone()));
+        }
+        issues.extend(validate_templates(platform));
+        for code in std::iter::once(&platform.code).chain(&platform.aliases) {
+            match seen.insert(code, platform.avatar) {
+                Some(avatar) if avatar != platform.avatar => {
+                    issues.push(RegistryIssue::ConflictingAvatar(code.clone()))
+                }
+                Some(_) => issues.push(RegistryIssue::DuplicateCode(code.clone())),
+                None => {}
+            }
+        }
+    }
+    issues
+}
+
+/// Variants compile and have distinct names, every placeholder can be filled:
+/// `<@>`, `<user>` and `<instance>` on the fediverse, or a named group of the
+/// variant's pattern
+fn validate_templates(platform: &Platform) -> Vec<RegistryIssue> {
+    let mut issues = vec![];
+    let mut fillable = vec!["@"];
+    if platform.fediverse {
+        fillable.extend(["user", "instance"]);
+    }
+    let mut templates = vec![(platform.url.as_deref().unwrap_or_default(), vec![])];
+    let mut names = HashSet::new();
+    for variant in &platform.variants {
+        let Ok(compiled) = variant.compile() else {
+            issues.push(RegistryIssue::InvalidVariant(platform.code.clone()));
+            continue;
+        };
+        if !names.insert(&variant.name) {
+            issues.push(RegistryIssue::InvalidVariant(platform.code.clone()));
+        }
+        let groups = compiled
+            .pattern
+            .iter()
+            .flat_map(|pattern| {
+                pattern
+                    .capture_names()
+                    .flatten()
+                    .map(|name| name.to_string())
+            })
+            .collect::<Vec<String>>();
+        templates.push((variant.url.as_str(), groups));
+    }
+    let unknown = templates.iter().any(|(template, groups)| {
+        placeholders(template)
+            .iter()
+            .any(|name| !fillable.contains(name) && !groups.iter().any(|group| group == name))
+    });
+    if unknown {
+        issues.push(RegistryIssue::UnknownPlaceholder(platform.code.clone()));
+    }
+    issues
+}
+
+impl Default for SupportedSocials {
+    fn default() -> SupportedSocials {
+        let mut new = SupportedSocials {
+            codes: HashMap::new(),
+            profiles: HashMap::new(),
+            avatars: HashSet::new(),
+            specials: HashSet::new(),
+            fediverse: HashSet::new(),
+            hosts: HashMap::new(),
+            reserved: HashMap::new(),
+            usernames: HashMap::new(),
+            variants: HashMap::new(),
+            avatar_priority: vec![],
+            favicon_provider: None,
+            favicon_domains: vec![],
+        };
+        new.extend_from_str(BUNDLED)
+            .expect("the bundled socials.toml should be valid");
+
+        new
+    }
+}
+
+type ProfileUrl = String;
+type Description = String;
+
+impl SupportedSocials {
+    /// The bundled registry, extended or overridden by the registry file at `path`
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<SupportedSocials> {
+        let raw = std::fs::read_to_string(&path)?;
+        let mut new = SupportedSocials::default();
+        new.extend_from_str(&raw).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.as_ref().display(), err),
+            )
+        })?;
+        Ok(new)
+    }
+
+    /// Adds every `[[platform]]` of a registry file, replacing any existing
+    /// entry with the same code. Nothing is added if the file has issues
+    pub fn extend_from_str(&mut self, raw: &str) -> Result<(), RegistryError> {
+        let registry: Registry = toml::from_str(raw).map_err(RegistryError::Toml)?;
+        let mut issues = validate(&registry.platform);
+        issues.extend(self.validate_merge(&registry.platform));
+        issues.extend(
+            registry
+                .avatar_priority
+                .iter()
+                .filter(|code| !self.is_avatar_source(code, &registry.platform))
+                .map(|code| RegistryIssue::UnknownAvatarPriority(code.clone())),
+        );
+        if let Some(code) = registry
+            .favicon_provider
+            .as_ref()
+            .filter(|code| !self.is_avatar_source(code, &registry.platform))
+        {
+            issues.push(RegistryIssue::UnknownFaviconProvider(code.clone()));
+        }
+        if !issues.is_empty() {
+            return Err(RegistryError::Invalid(issues));
+        }
+        registry
+            .platform
+            .into_iter()
+            .for_each(|platform| self.insert(platform));
+        if !registry.avatar_priority.is_empty() {
+            self.avatar_priority = registry
+                .avatar_priority
+                .iter()
+                .map(|code| self.resolve(code).to_string())
+                .collect();
+        }
+        if let Some(code) = registry.favicon_provider {
+            self.favicon_provider = Some(self.resolve(&code).to_string());
+        }
+        if let Some(domains) = registry.favicon_domains {
+            self.favicon_domains = domains;
+        }
+        Ok(())
+    }
+
+    /// Checks the platforms against the registry they're added to: an entry
+    /// can override a platform under its code, not take another one's code
+    /// or alias
+    fn validate_merge(&self, platforms: &[Platform]) -> Vec<RegistryIssue> {
+        platforms
+            .iter()
+            .flat_map(|platform| {
+                std::iter::once(&platform.code)
+                    .chain(&platform.aliases)
+                    .filter_map(|name| match self.codes.get(name) {
+                        Some(owner) if *owner != platform.code => {
+                            Some(RegistryIssue::TakenCode(name.clone(), owner.clone()))
+                        }
+                        _ => None,
+                    })
+            })
+            .collect()
+    }
+
+    /// The provider to take the favicon of `host` from, if its domain is
+    /// allowed: `favicon_domains` entries are a domain, `*.domain` for its
+    /// subdomains or `*` for any, negated with a leading `!`, the last
+    /// matching entry wins
+    pub fn favicon(&self, host: &str) -> Option<&str> {
+        let host = host.to_ascii_lowercase();
+        let host = host.strip_prefix("www.").unwrap_or(&host);
+        let allowed = self
+            .favicon_domains
+            .iter()
+            .rev()
+            .find_map(|entry| {
+                let (allow, pattern) = match entry.strip_prefix('!') {
+                    Some(pattern) => (false, pattern),
+                    None => (true, entry.as_str()),
+                };
+                let matches = match pattern.strip_prefix("*.") {
+                    _ if pattern == "*" => true,
+                    Some(domain) => host.ends_with(&format!(".{}", domain)),
+                    None => host == pattern,
+                };
+                matches.then_some(allow)
+            })
+            .unwrap_or(false);
+        match allowed {
+            true => self.favicon_provider.as_deref(),
+            false => None,
+        }
+    }
+
+    /// Whether an avatar can be inferred from `code`, as defined by the
+    /// `platforms` about to be inserted or else by the registry
+    fn is_avatar_source(&self, code: &str, platforms: &[Platform]) -> bool {
+        match platforms
+            .iter()
+            .rfind(|platform| platform.code == code || platform.aliases.iter().any(|a| a == code))
+        {
+            Some(platform) => platform.avatar || platform.fediverse,
+            None => self.is_unavatar_supported(code) || self.is_fediverse(code),
+        }
+    }
+
+    /// Where the code is in the avatar priority list, unlisted codes last
+    pub(crate) fn avatar_rank(&self, code: &str) -> usize {
+        let code = self.resolve(code);
+        self.avatar_priority
+            .iter()
+            .position(|listed| listed == code)
+            .unwrap_or(self.avatar_priority.len())
+    }
+
+    /// Registers the platform under its code, its aliases resolve to it
+    pub fn insert(&mut self, platform: Platform) {
+        let code = platform.code;
+        std::iter::once(&code)
+            .chain(&platform.aliases)
+            .for_each(|name| {
+                self.profiles.remove(name);
+                self.avatars.remove(name);
+                self.specials.remove(name);
+                self.fediverse.remove(name);
+                self.hosts.remove(name);
+                self.reserved.remove(name);
+                self.usernames.remove(name);
+                self.variants.remove(name);
+                self.codes.insert(name.clone(), code.clone());
+            });
+        if platform.special {
+            self.specials.insert(code.clone());
+        }
+        if platform.fediverse {
+            self.fediverse.insert(code.clone());
+        }
+        if platform.avatar {
+            self.avatars.insert(code.clone());
+        }
+        if let Some(Ok(rule)) = platform.username.as_ref().map(|rule| rule.compile()) {
+            self.usernames.insert(code.clone(), rule);
+        }
+        let variants = platform
+            .variants
+            .iter()
+            .filter_map(|variant| variant.compile().ok())
+            .collect::<Vec<CompiledVariant>>();
+        if !variants.is_empty() {
+            self.variants.insert(code.clone(), variants);
+        }
+        if !platform.hosts.is_empty() {
+            self.hosts.insert(code.clone(), platform.hosts);
+        }
+        if !platform.reserved.is_empty() {
+            let reserved = platform.reserved.iter().map(|path| path.to_lowercase());
+            self.reserved.insert(code.clone(), reserved.collect());
+        }
+        if let Some(url) = platform.url {
+            self.profiles.insert(code, (platform.name, url));
+        }
+    }
+
+    pub(crate) fn username_rule(&self, code: &str) -> Option<&CompiledRule> {
+        self.usernames.get(self.resolve(code))
+    }
+
+    /// Each profile platform's code, variant name, URL template and other
+    /// hostnames, the default template has no variant name
+    pub(crate) fn templates(&self) -> impl Iterator<Item = (&str, Option<&str>, &str, &[String])> {
+        self.profiles.iter().flat_map(|(code, (_, template))| {
+            let hosts = self.hosts.get(code).map(|hosts| hosts.as_slice());
+            let hosts = hosts.unwrap_or_default();
+            std::iter::once((code.as_str(), None, template.as_str(), hosts)).chain(
+                self.variants
+                    .get(code)
+                    .into_iter()
+                    .flatten()
+                    .map(move |variant| {
+                        (
+                            code.as_str(),
+                            Some(variant.name.as_str()),
+                            variant.url.as_str(),
+                            hosts,
+                        )
+                    }),
+            )
+        })
+    }
+
+    /// If `username` is a path of the platform that isn't a profile
+    pub(crate) fn is_reserved(&self, code: &str, username: &str) -> bool {
+        self.reserved
+            .get(self.resolve(code))
+            .is_some_and(|reserved| reserved.contains(&username.to_lowercase()))
+    }
+
+    /// Names of the platform's variants
+    pub fn variants(&self, code: &str) -> Vec<&str> {
+        self.variants
+            .get(self.resolve(code))
+            .into_iter()
+            .flatten()
+            .map(|variant| variant.name.as_str())
+            .collect()
+    }
+
+    /// The variant named `variant`, or the first one whose pattern matches
+    /// the username. None for the default template
+    pub(crate) fn pick_variant(
+        &self,
+        code: &str,
+        username: &str,
+        variant: Option<&str>,
+    ) -> Result<Option<&CompiledVariant>, Error> {
+        let code = self.resolve(code);
+        let mut variants = self.variants.get(code).into_iter().flatten();
+        match variant {
+            Some(name) => match variants.find(|variant| variant.name == name) {
+                Some(variant) => Ok(Some(variant)),
+                None => Err(Error::new(
+                    ErrorKind::UnknownVariant(code.to_string(), name.to_string()),
+                    0..0,
+                )),
+            },
+            None => Ok(variants.find(|variant| {
+                variant
+                    .pattern
+                    .as_ref()
+                    .is_some_and(|pattern| pattern.is_match(username))
+            })),
+        }
+    }
+
+    /// The canonical code for a code or one of its aliases
+    pub fn canonical(&self, code: &str) -> Option<&str> {
+        self.codes.get(code).map(|code| code.as_str())
+    }
+
+    fn resolve<'a>(&'a self, code: &'a str) -> &'a str {
+        self.canonical(code).unwrap_or(code)
+    }
+
+    /// Returns the display name of the social and the formatted profile URL.
+    /// The description will be use to override the display name.
+    pub fn get(
+        &self,
+        social_username: &str,
+        social_code: &str,
+        description: &Option<String>,
+    ) -> Result<(ProfileUrl, Description), Error> {
+        self.get_variant(social_username, social_code, None, description)
+    }
+
+    /// Same as get(), with the URL template of a variant. Without a variant
+    /// name, the username can still pick one
+    pub fn get_variant(
+        &self,
+        social_username: &str,
+        social_code: &str,
+        variant: Option<&str>,
+        description: &Option<String>,
+    ) -> Result<(ProfileUrl, Description), Error> {
+        let social_code = self.resolve(social_code);
+        let Some((social_name, template)) = self.profiles.get(social_code) else {
+            return Err(Error::new(
+                ErrorKind::UnsupportedSocialCode(social_code.to_string()),
+                0..0,
+            ));
+        };
+        let mut values = HashMap::from([("@", social_username.to_string())]);
+        // Fediverse handles are `user@instance`
+        if let Some((user, instance)) = social_username
+            .split_once('@')
+            .filter(|_| self.is_fediverse(social_code))
+        {
+            values.insert("user", user.to_string());
+            values.insert("instance", instance.to_string());
+        }
+        let profile_url = match self.pick_variant(social_code, social_username, variant)? {
+            Some(variant) => {
+                let captures = variant.captures(social_username);
+                values.extend(
+                    captures
+                        .iter()
+                        .map(|(name, value)| (name.as_str(), value.clone())),
+                );
+                fill_template(&variant.url, &values)
+            }
+            None => fill_template(template, &values),
+        };
+        match description {
+            None => Ok((format!("//{}", profile_url), social_name.clone())),
+            Some(description) => Ok((
+                format!("//{}", profile_url),
+                format!("{} | {}", social_name, description.clone()),
+            )),
+        }
+    }
+
+    // Returns true if the social code is artists' own link-in-bio
+    pub fn is_special(&self, social_code: &Option<String>) -> bool {
+        match social_code {
+            Some(code) => self.specials.contains(self.resolve(code)),
+            None => false,
+        }
+    }
+
+    /// Returns true if the social code takes `user@instance` handles
+    pub fn is_fediverse(&self, code: &str) -> bool {
+        self.fediverse.contains(self.resolve(code))
+    }
+
+    // Returns true if the social code is supported by unavatar
+    pub fn is_unavatar_supported(&self, code: &str) -> bool {
+        self.avatars.contains(self.resolve(code))
+    }
+
+    /// Returns true if the code is an avatar provider without profile pages
+    pub fn is_avatar_only(&self, code: &str) -> bool {
+        let code = self.resolve(code);
+        self.avatars.contains(code) && !self.profiles.contains_key(code)
+    }
+
+    /// Up to `n` profile platform codes closest to `code`, closest first
+    pub fn closest(&self, code: &str, n: usize) -> Vec<&str> {
+        closest(
+            self.codes
+                .iter()
+                .filter(|(_, canonical)| self.profiles.contains_key(*canonical))
+                .map(|(code, _)| code),
+            code,
+            n,
+        )
+    }
+
+    /// Up to `n` avatar provider codes closest to `code`, closest first
+    pub fn closest_avatar(&self, code: &str, n: usize) -> Vec<&str> {
+        closest(
+            self.codes
+                .iter()
+                .filter(|(_, canonical)| self.avatars.contains(*canonical))
+                .map(|(code, _)| code),
+            code,
+            n,
+        )
+    }
+
+    /// Returns true if the code is a platform with profile pages
+    pub fn is_supported(&self, code: &str) -> bool {
+        self.profiles.contains_key(self.resolve(code))
+    }
+}
+
+fn closest<'a>(codes: impl Iterator<Item = &'a SocialCode>, code: &str, n: usize) -> Vec<&'a str> {
+    let mut candidates = codes
+        .map(|candidate| (strsim::levenshtein(code, candidate), candidate.as_str()))
+        .filter(|(distance, _)| *distance <= 2)
+        .collect::<Vec<(usize, &str)>>();
+    candidates.sort();
+    candidates.dedup();
+    candidates
+        .into_iter()
+        .take(n)
+        .map(|(_, code)| code)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get() {
+        let supported_socials = SupportedSocials::default();
+
+        let (profile_url, description) = supported_socials
+            .get("username", "twitter", &None)
+            .expect("twitter should be supported");
+        assert_eq!(profile_url, "//twitter.com/username");
+        assert_eq!(description, "𝕏");
+
+        let (profile_url, description) = supported_socials
+            .get("username", "twitter", &Some("description".to_string()))
+            .expect("twitter should be supported");
+        assert_eq!(profile_url, "//twitter.com/username");
+        assert_eq!(description, "𝕏 | description");
+
+        let (profile_url, description) = supported_socials
+            .get("username", "fa", &None)
+            .expect("fa should be supported");
+        assert_eq!(profile_url, "//www.furaffinity.net/user/username/");
+        assert_eq!(description, "FurAffinity 🐾");
+
+        let (profile_url, description) = supported_socials
+            .get("username", "fa", &Some("description".to_string()))
+            .expect("fa should be supported");
+        assert_eq!(profile_url, "//www.furaffinity.net/user/username/");
+        assert_eq!(description, "FurAffinity 🐾 | description");
+
+        let (profile_url, description) = supported_socials
+            .get("artist@mastodon.art", "fedi", &None)
+            .expect("fedi should be supported");
+        assert_eq!(profile_url, "//mastodon.art/@artist");
+        assert_eq!(description, "Fediverse");
+
+        let err = supported_socials
+            .get("username", "unknown", &None)
+            .expect_err("unknown should not be supported");
+        assert_eq!(
+            err.kind,
+            ErrorKind::UnsupportedSocialCode("unknown".to_string())
+        );
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut supported_socials = SupportedSocials::default();
+        supported_socials
+            .extend_from_str(
+                r#"
+                [[platform]]
+                code = "cara"
+                aliases = ["cara.app"]
+                name = "Cara"
+                url = "cara.app/<@>"
+
+                [[platform]]
+                code = "fa"
+                name = "FurAffinity"
+                url = "furaffinity.net/user/<@>"
+                special = true
+                "#,
+            )
+            .unwrap();
+
+        let (profile_url, _) = supported_socials
+            .get("username", "cara.app", &None)
+            .unwrap();
+        assert_eq!(profile_url, "//cara.app/username");
+        assert!(!supported_socials.is_unavatar_supported("cara"));
+
+        let (profile_url, description) = supported_socials.get("username", "fa", &None).unwrap();
+        assert_eq!(profile_url, "//furaffinity.net/user/username");
+        assert_eq!(description, "FurAffinity");
+        assert!(supported_socials.is_special(&Some("fa".to_string())));
+
+        assert!(supported_socials
+            .extend_from_str("[[platform]]\ncode = \"nameless\"")
+            .is_err());
+    }
+
+    #[test]
+    fn bundled_registry_is_clean() {
+        let registry: Registry = toml::from_str(BUNDLED).unwrap();
+        assert_eq!(validate(&registry.platform), vec![]);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut supported_socials = SupportedSocials::default();
+        let err = supported_socials
+            .extend_from_str(
+                r#"
+                [[platform]]
+                code = "cara"
+                name = "Cara"
+                url = ""
+
+                [[platform]]
+                code = "vgen"
+                aliases = ["cara"]
+                name = "VGen"
+                url = "vgen.co/<@>"
+
+                [[platform]]
+                code = "vgen"
+                name = "VGen"
+                url = "vgen.co/<@>"
+                avatar = true
+                username = { pattern = "[" }
+                "#,
+            )
+            .unwrap_err();
+        let RegistryError::Invalid(issues) = err else {
+            panic!("expected registry issues, got {:?}", err);
+        };
+        assert_eq!(
+            issues,
+            vec![
+                RegistryIssue::EmptyTemplate("cara".to_string()),
+                RegistryIssue::DuplicateCode("cara".to_string()),
+                RegistryIssue::InvalidUsernameRule("vgen".to_string()),
+                RegistryIssue::ConflictingAvatar("vgen".to_string()),
+            ]
+        );
+        assert!(!supported_socials.is_supported("vgen"));
+    }
+
+    #[test]
+    fn test_validate_merged() {
+        let mut supported_socials = SupportedSocials::default();
+        let err = supported_socials
+            .extend_from_str(
+                r#"
+                [[platform]]
+                code = "xcom"
+                aliases = ["x"]
+                name = "X"
+                url = "x.com/<@>"
+
+                [[platform]]
+                code = "bsky"
+                name = "Bluesky"
+                url = "bsky.app/profile/<@>"
+                "#,
+            )
+            .unwrap_err();
+        let RegistryError::Invalid(issues) = err else {
+            panic!("expected registry issues, got {:?}", err);
+        };
+        assert_eq!(
+            issues,
+            vec![
+                RegistryIssue::TakenCode("x".to_string(), "twitter".to_string()),
+                RegistryIssue::TakenCode("bsky".to_string(), "bluesky".to_string()),
+            ]
+        );
+        assert_eq!(supported_socials.canonical("x"), Some("twitter"));
+        assert!(supported_socials.get("foo", "twitter", &None).is_ok());
+
+        // Overriding a platform under its own code keeps its aliases
+        supported_socials
+            .extend_from_str(
+                r#"
+                [[platform]]
+                code = "twitter"
+                aliases = ["x"]
+                name = "Twitter"
+                url = "x.com/<@>"
+                "#,
+            )
+            .unwrap();
+        let (profile_url, _) = supported_socials.get("foo", "x", &None).unwrap();
+        assert_eq!(profile_url, "//x.com/foo");
+    }
+
+    #[test]
+    fn test_variants() {
+        let mut supported_socials = SupportedSocials::default();
+        let url = |username: &str, code: &str, variant: Option<&str>| {
+            supported_socials
+                .get_variant(username, code, variant, &None)
+                .map(|(url, _)| url)
+        };
+
+        assert_eq!(
+            url("UCaaaaaaaaaaaaaaaaaaaaaa", "youtube", None).unwrap(),
+            "//youtube.com/channel/UCaaaaaaaaaaaaaaaaaaaaaa"
+        );
+        assert_eq!(
+            url("foo", "youtube", Some("custom")).unwrap(),
+            "//youtube.com/c/foo"
+        );
+        assert_eq!(
+            url("あ", "pixiv", Some("vanity")).unwrap(),
+            "//pixiv.me/%E3%81%82"
+        );
+        assert_eq!(
+            url("foo", "youtube", Some("nope")).unwrap_err().kind,
+            ErrorKind::UnknownVariant("youtube".to_string(), "nope".to_string())
+        );
+        assert_eq!(
+            supported_socials.variants("youtube"),
+            vec!["channel", "custom"]
+        );
+
+        supported_socials
+            .extend_from_str(
+                r#"
+                [[platform]]
+                code = "vgen"
+                name = "VGen"
+                url = "vgen.co/<@>"
+
+                [[platform.variants]]
+                name = "commission"
+                url = "vgen.co/<user>/commission/<id>"
+                match = "^(?P<user>[^/]+)/(?P<id>[0-9]+)$"
+                "#,
+            )
+            .unwrap();
+        assert_eq!(
+            supported_socials
+                .get_variant("foo/12", "vgen", None, &None)
+                .unwrap()
+                .0,
+            "//vgen.co/foo/commission/12"
+        );
+
+        let err = supported_socials
+            .extend_from_str(
+                r#"
+                [[platform]]
+                code = "cara"
+                name = "Cara"
+                url = "cara.app/<id>"
+
+                [[platform.variants]]
+                name = "a"
+                url = "cara.app/<@>"
+                match = "("
+                "#,
+            )
+            .unwrap_err();
+        let RegistryError::Invalid(issues) = err else {
+            panic!("expected registry issues, got {:?}", err);
+        };
+        assert_eq!(
+            issues,
+            vec![
+                RegistryIssue::InvalidVariant("cara".to_string()),
+                RegistryIssue::UnknownPlaceholder("cara".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_avatar_priority() {
+        let mut supported_socials = SupportedSocials::default();
+        assert!(supported_socials.avatar_rank("bsky") < supported_socials.avatar_rank("x"));
+        assert_eq!(
+            supported_socials.avatar_rank("reddit"),
+            supported_socials.avatar_rank("telegram")
+        );
+
+        supported_socials
+            .extend_from_str(r#"avatar_priority = ["x", "bsky"]"#)
+            .unwrap();
+        assert_eq!(supported_socials.avatar_rank("twitter"), 0);
+        assert_eq!(supported_socials.avatar_rank("bluesky"), 1);
+        assert_eq!(supported_socials.avatar_rank("instagram"), 2);
+
+        let err = supported_socials
+            .extend_from_str(r#"avatar_priority = ["patreon", "nope"]"#)
+            .unwrap_err();
+        let RegistryError::Invalid(issues) = err else {
+            panic!("expected registry issues, got {:?}", err);
+        };
+        assert_eq!(
+            issues,
+            vec![
+                RegistryIssue::UnknownAvatarPriority("patreon".to_string()),
+                RegistryIssue::UnknownAvatarPriority("nope".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_favicon() {
+        let mut supported_socials = SupportedSocials::default();
+        assert_eq!(
+            supported_socials.favicon("www.example.com"),
+            Some("duckduckgo")
+        );
+
+        supported_socials
+            .extend_from_str(
+                r#"
+                favicon_provider = "google"
+                favicon_domains = ["*", "!shop.com", "!*.shop.com", "me.shop.com"]
+                "#,
+            )
+            .unwrap();
+        assert_eq!(supported_socials.favicon("example.com"), Some("google"));
+        assert_eq!(supported_socials.favicon("Shop.com"), None);
+        assert_eq!(supported_socials.favicon("foo.shop.com"), None);
+        assert_eq!(supported_socials.favicon("me.shop.com"), Some("google"));
+
+        supported_socials
+            .extend_from_str(r#"favicon_domains = []"#)
+            .unwrap();
+        assert_eq!(supported_socials.favicon("example.com"), None);
+
+        let err = supported_socials
+            .extend_from_str(r#"favicon_provider = "patreon""#)
+            .unwrap_err();
+        let RegistryError::Invalid(issues) = err else {
+            panic!("expected registry issues, got {:?}", err);
+        };
+        assert_eq!(
+            issues,
+            vec![RegistryIssue::UnknownFaviconProvider("patreon".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_avatar_only() {
+        let supported_socials = SupportedSocials::default();
+
+        for code in ["gravatar", "google", "duckduckgo", "microlink"] {
+            assert!(supported_socials.is_avatar_only(code));
+            assert!(!supported_socials.is_supported(code));
+            assert!(supported_socials.get("username", code, &None).is_err());
+        }
+        assert!(supported_socials.is_unavatar_supported("github"));
+        assert!(!supported_socials.is_avatar_only("github"));
+        assert!(!supported_socials.is_unavatar_supported("fa"));
+        assert_eq!(
+            supported_socials.closest_avatar("gravatr", 1),
+            vec!["gravatar"]
+        );
+        assert_eq!(supported_socials.closest("gravatr", 1), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_aliases() {
+        let supported_socials = SupportedSocials::default();
+
+        assert_eq!(supported_socials.canonical("x"), Some("twitter"));
+        assert_eq!(supported_socials.canonical("twitter"), Some("twitter"));
+        assert_eq!(supported_socials.canonical("fb"), Some("facebook"));
+        assert_eq!(supported_socials.canonical("bsky"), Some("bluesky"));
+        assert_eq!(supported_socials.canonical("unknown"), None);
+        assert!(supported_socials.is_unavatar_supported("x"));
+        assert_eq!(
+            supported_socials.get("username", "fb", &None).unwrap(),
+            supported_socials
+                .get("username", "facebook", &None)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_is_special() {
+        let supported_socials = SupportedSocials::default();
+
+        assert!(supported_socials.is_special(&Some("potofu.me".to_string())));
+        assert!(supported_socials.is_special(&Some("carrd.co".to_string())));
+        assert!(supported_socials.is_special(&Some("linktr.ee".to_string())));
+        assert!(supported_socials.is_special(&Some("lit.link".to_string())));
+
+        assert!(!supported_socials.is_special(&Some("unknown".to_string())));
+        assert!(!supported_socials.is_special(&None));
+    }
+}
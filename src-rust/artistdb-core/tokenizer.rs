@@ -0,0 +1,205 @@
+//! Escape grammar shared by the info line and the social lines:
+//! - `\,` a literal `,`, doesn't split components
+//! - `\\` a literal `\`
+//! - `\*` a literal `*`, doesn't mark a social as special
+//! - `\@` a literal `@`, doesn't split the username and the social code
+//! - `\#` a literal `#`, doesn't start a comment
+//! - `\n` a newline
+//!
+//! Anything else after a `\`, or a `\` ending the line, is an invalid escape.
+
+use std::ops::Range;
+
+use crate::error::{Error, ErrorKind};
+
+/// A comma-separated piece of a line, still escaped, and where it is in that
+/// line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Token<'a> {
+    raw: &'a str,
+    pub span: Range<usize>,
+}
+
+impl<'a> Token<'a> {
+    fn new(line: &'a str, span: Range<usize>) -> Token<'a> {
+        let untrimmed = &line[span.clone()];
+        let raw = untrimmed.trim();
+        let start = span.start + (untrimmed.len() - untrimmed.trim_start().len());
+        Token {
+            raw,
+            span: start..start + raw.len(),
+        }
+    }
+
+    /// The token as written, escapes included
+    pub fn raw(&self) -> &'a str {
+        self.raw
+    }
+
+    /// Byte indexes of `target` in raw(), skipping escaped ones
+    fn find_unescaped(&self, target: char) -> Vec<usize> {
+        let mut found = vec![];
+        let mut escaped = false;
+        for (i, c) in self.raw.char_indices() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                _ if c == target => found.push(i),
+                _ => {}
+            }
+        }
+        found
+    }
+
+    fn slice(&self, range: Range<usize>) -> Token<'a> {
+        Token {
+            raw: &self.raw[range.clone()],
+            span: self.span.start + range.start..self.span.start + range.end,
+        }
+    }
+
+    /// The rest of the token if it starts with an unescaped `prefix`
+    pub fn strip_prefix(&self, prefix: char) -> Option<Token<'a>> {
+        match self.raw.starts_with(prefix) {
+            true => Some(self.slice(prefix.len_utf8()..self.raw.len())),
+            false => None,
+        }
+    }
+
+    /// Split on the first unescaped `delimiter`
+    pub fn split_once(&self, delimiter: char) -> Option<(Token<'a>, Token<'a>)> {
+        let i = *self.find_unescaped(delimiter).first()?;
+        Some((
+            self.slice(0..i),
+            self.slice(i + delimiter.len_utf8()..self.raw.len()),
+        ))
+    }
+
+    /// Split on the last unescaped `delimiter`
+    pub fn rsplit_once(&self, delimiter: char) -> Option<(Token<'a>, Token<'a>)> {
+        let i = *self.find_unescaped(delimiter).last()?;
+        Some((
+            self.slice(0..i),
+            self.slice(i + delimiter.len_utf8()..self.raw.len()),
+        ))
+    }
+
+    /// The token with its escapes resolved
+    pub fn unescape(&self) -> String {
+        let mut value = String::with_capacity(self.raw.len());
+        let mut chars = self.raw.chars();
+        while let Some(c) = chars.next() {
+            match (c, c == '\\') {
+                (_, true) => match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some(escaped) => value.push(escaped),
+                    None => {}
+                },
+                (c, false) => value.push(c),
+            }
+        }
+        value
+    }
+}
+
+/// Split a line by unescaped `,` into trimmed tokens, validating its escapes
+pub(crate) fn tokenize(line: &str) -> Result<Vec<Token<'_>>, Error> {
+    let mut tokens = vec![];
+    let mut start = 0;
+    let mut chars = line.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some((_, ',' | '\\' | '*' | '@' | '#' | 'n')) => {}
+                Some((j, other)) => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidEscape,
+                        i..j + other.len_utf8(),
+                    ))
+                }
+                None => return Err(Error::new(ErrorKind::InvalidEscape, i..i + 1)),
+            },
+            ',' => {
+                tokens.push(Token::new(line, start..i));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    tokens.push(Token::new(line, start..line.len()));
+
+    Ok(tokens)
+}
+
+/// Escape `value` so it tokenizes back to itself: `\`, `,`, newlines, `#`
+/// that would start a comment, plus every char in `extra`
+pub(crate) fn escape(value: &str, extra: &[char]) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    let mut previous: Option<char> = None;
+    for c in value.chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\\' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '#' if previous.is_none_or(|previous| previous.is_whitespace()) => {
+                escaped.push_str("\\#")
+            }
+            _ if extra.contains(&c) => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+        previous = Some(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenize_spans() {
+        let tokens = tokenize("foo, bar\\, baz ,qux\\\\").unwrap();
+        let values = tokens
+            .iter()
+            .map(|token| token.unescape())
+            .collect::<Vec<String>>();
+        assert_eq!(values, vec!["foo", "bar, baz", "qux\\"]);
+        assert_eq!(tokens[1].span, 5..14);
+        assert_eq!(tokens[2].span, 16..21);
+    }
+
+    #[test]
+    fn tokenize_invalid_escapes() {
+        let err = tokenize("foo,bar\\").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidEscape);
+        assert_eq!(err.span, 7..8);
+
+        let err = tokenize("foo\\x,bar").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidEscape);
+        assert_eq!(err.span, 3..5);
+    }
+
+    #[test]
+    fn split_unescaped() {
+        let tokens = tokenize("\\*foo\\@bar@twitter").unwrap();
+        assert!(tokens[0].strip_prefix('*').is_none());
+
+        let (username, code) = tokens[0].split_once('@').unwrap();
+        assert_eq!(username.unescape(), "*foo@bar");
+        assert_eq!(code.unescape(), "twitter");
+        assert_eq!(code.span, 11..18);
+    }
+
+    #[test]
+    fn escape_round_trip() {
+        let value = "a\\b, #c#d\ne*f@g";
+        let escaped = escape(value, &['*', '@']);
+        assert_eq!(escaped, "a\\\\b\\, \\#c#d\\ne\\*f\\@g");
+        assert_eq!(tokenize(&escaped).unwrap()[0].unescape(), value);
+    }
+}
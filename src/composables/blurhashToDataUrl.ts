@@ -0,0 +1,81 @@
+const BASE83 = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz#$%*+,-.:;=?@[]^_{|}~";
+
+function decode83(value: string): number {
+	let decoded = 0;
+	for (const char of value) {
+		decoded = decoded * 83 + BASE83.indexOf(char);
+	}
+	return decoded;
+}
+
+function srgbToLinear(value: number): number {
+	const v = value / 255;
+	return v <= 0.04045 ? v / 12.92 : Math.pow((v + 0.055) / 1.055, 2.4);
+}
+
+function linearToSrgb(value: number): number {
+	const v = Math.max(0, Math.min(1, value));
+	return Math.round(v <= 0.0031308 ? v * 12.92 * 255 : (1.055 * Math.pow(v, 1 / 2.4) - 0.055) * 255);
+}
+
+function signPow(value: number, exp: number): number {
+	return Math.sign(value) * Math.pow(Math.abs(value), exp);
+}
+
+/** Render a blurhash, precomputed by the encoder, to a tiny PNG data URL. Empty on invalid hashes */
+export function blurhashToDataUrl(blurhash: string, size = 32): string {
+	if (blurhash.length < 6) {
+		return "";
+	}
+	const sizeFlag = decode83(blurhash[0]);
+	const componentsX = (sizeFlag % 9) + 1;
+	const componentsY = Math.floor(sizeFlag / 9) + 1;
+	if (blurhash.length !== 4 + 2 * componentsX * componentsY) {
+		return "";
+	}
+	const maxValue = (decode83(blurhash[1]) + 1) / 166;
+
+	const dc = decode83(blurhash.slice(2, 6));
+	const colors: Array<[number, number, number]> = [
+		[srgbToLinear(dc >> 16), srgbToLinear((dc >> 8) & 255), srgbToLinear(dc & 255)],
+	];
+	for (let i = 1; i < componentsX * componentsY; i++) {
+		const ac = decode83(blurhash.slice(4 + i * 2, 6 + i * 2));
+		colors.push([
+			signPow((Math.floor(ac / (19 * 19)) - 9) / 9, 2) * maxValue,
+			signPow(((Math.floor(ac / 19) % 19) - 9) / 9, 2) * maxValue,
+			signPow(((ac % 19) - 9) / 9, 2) * maxValue,
+		]);
+	}
+
+	const canvas = document.createElement("canvas");
+	canvas.width = size;
+	canvas.height = size;
+	const context = canvas.getContext("2d");
+	if (context === null) {
+		return "";
+	}
+	const pixels = context.createImageData(size, size);
+	for (let y = 0; y < size; y++) {
+		for (let x = 0; x < size; x++) {
+			let [r, g, b] = [0, 0, 0];
+			for (let j = 0; j < componentsY; j++) {
+				for (let i = 0; i < componentsX; i++) {
+					const basis =
+						Math.cos((Math.PI * x * i) / size) * Math.cos((Math.PI * y * j) / size);
+					const color = colors[i + j * componentsX];
+					r += color[0] * basis;
+					g += color[1] * basis;
+					b += color[2] * basis;
+				}
+			}
+			const offset = 4 * (x + y * size);
+			pixels.data[offset] = linearToSrgb(r);
+			pixels.data[offset + 1] = linearToSrgb(g);
+			pixels.data[offset + 2] = linearToSrgb(b);
+			pixels.data[offset + 3] = 255;
+		}
+	}
+	context.putImageData(pixels, 0, 0);
+	return canvas.toDataURL();
+}
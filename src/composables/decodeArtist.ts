@@ -0,0 +1,101 @@
+/** Mirrors `artistdb-core/frontend.rs`, bump together */
+const FORMAT_HEADER = "artistdb 4";
+
+export type Social = {
+	isSpecial: boolean;
+	link: string;
+	desc: string;
+};
+
+/** `srcset` is empty for avatars in one size only */
+export type Avatar = {
+	src: string;
+	srcset: string;
+};
+
+export type ArtistInfo = {
+	displayName: string;
+	/** `#rrggbb` and blurhash painted until the avatar loads, empty if unknown */
+	colour: string;
+	blurhash: string;
+	/** Tried in order, `fallbackAvatar` once none of them loads */
+	avatars: Array<Avatar>;
+	fallbackAvatar: string;
+	socials: Array<Social>;
+};
+
+/** Split by unescaped `,` and resolve `\\`, `\,`, `\*`, `\n` and `\r` */
+export function decodeFields(line: string): Array<string> {
+	const fields = [""];
+	for (let i = 0; i < line.length; i++) {
+		const char = line[i];
+		if (char === ",") {
+			fields.push("");
+			continue;
+		}
+		if (char !== "\\") {
+			fields[fields.length - 1] += char;
+			continue;
+		}
+		i++;
+		switch (line[i]) {
+			case "n":
+				fields[fields.length - 1] += "\n";
+				break;
+			case "r":
+				fields[fields.length - 1] += "\r";
+				break;
+			case "\\":
+			case ",":
+			case "*":
+				fields[fields.length - 1] += line[i];
+				break;
+			default:
+				throw new Error(`invalid escape: ${line}`);
+		}
+	}
+	return fields;
+}
+
+/** A URL, or the `<url> <width>w` pairs of a local avatar's sizes */
+export function decodeAvatar(field: string): Avatar {
+	const parts = field.split(" ");
+	const isSrcset =
+		parts.length >= 2 &&
+		parts.length % 2 === 0 &&
+		parts.every((part, i) => i % 2 === 0 || /^\d+w$/.test(part));
+	if (!isSrcset) {
+		return { src: field, srcset: "" };
+	}
+
+	const candidates: Array<string> = [];
+	for (let i = 0; i < parts.length; i += 2) {
+		candidates.push(`${parts[i]} ${parts[i + 1]}`);
+	}
+	// The largest one for browsers without `srcset`
+	return { src: parts[parts.length - 2], srcset: candidates.join(", ") };
+}
+
+export function decodeArtist(content: string): ArtistInfo {
+	const lines = content.split("\n");
+	if (lines[0] !== FORMAT_HEADER) {
+		throw new Error(`unsupported format: ${lines[0]}`);
+	}
+
+	const [displayName, colour, blurhash, fallbackAvatar, ...avatars] = decodeFields(lines[1] ?? "");
+
+	const socials = lines.slice(2).map((line) => {
+		const isSpecial = line.startsWith("*");
+		const [link, desc] = decodeFields(isSpecial ? line.slice(1) : line);
+		return { isSpecial, link, desc };
+	});
+
+	return {
+		displayName,
+		colour,
+		blurhash,
+		avatars: avatars.map(decodeAvatar),
+		fallbackAvatar,
+		socials,
+	};
+}
@@ -0,0 +1,105 @@
+/** Mirrors `artistdb-core/search_index.rs`, bump together */
+const INDEX_HEADER = "artistdb-index 1";
+
+export type IndexEntry = {
+	username: string;
+	displayName: string;
+	aliases: Array<string>;
+	/** `username@code` of every platform the artist is on */
+	handles: Array<string>;
+};
+
+/** Entries sorted by username */
+export function decodeIndex(content: string): Array<IndexEntry> {
+	const lines = content.split("\n");
+	if (lines[0] !== INDEX_HEADER) {
+		throw new Error(`unsupported format: ${lines[0]}`);
+	}
+
+	return lines.slice(1).map((line) => {
+		const [username, displayName, ...rest] = decodeFields(line);
+		return {
+			username,
+			displayName: displayName ?? username,
+			aliases: rest.filter((field) => !field.includes("@")),
+			handles: rest.filter((field) => field.includes("@")),
+		};
+	});
+}
+
+/** A term a query can be a prefix of, lowercased, and the entry it's from */
+type SearchTerm = {
+	term: string;
+	/** Position of the entry, so in username order */
+	entry: number;
+	/** The username or an alias, ranked first */
+	byName: boolean;
+};
+
+export type SearchIndex = {
+	entries: Array<IndexEntry>;
+	/** Sorted, so the terms starting with a query are one range */
+	terms: Array<SearchTerm>;
+};
+
+/** Terms of the entry a query can be a prefix of, lowercased */
+function searchTerms(entry: IndexEntry): Array<string> {
+	const displayName = entry.displayName.toLowerCase();
+	return [
+		entry.username,
+		...entry.aliases,
+		displayName,
+		...displayName.split(/\s+/),
+		// The platform username, without the instance and the code
+		...entry.handles.map((handle) => handle.split("@")[0].toLowerCase()),
+	];
+}
+
+/** Sort the terms of every entry once, so each search is a binary search */
+export function buildSearchIndex(entries: Array<IndexEntry>): SearchIndex {
+	const terms = entries.flatMap((entry, i) => {
+		const nameTerms = 1 + entry.aliases.length;
+		return searchTerms(entry).map((term, j) => ({ term, entry: i, byName: j < nameTerms }));
+	});
+	// By code unit, like the comparisons of the binary search
+	terms.sort((a, b) => (a.term < b.term ? -1 : a.term > b.term ? 1 : 0));
+	return { entries, terms };
+}
+
+/** Position of the first term not before `needle` */
+function lowerBound(terms: Array<SearchTerm>, needle: string): number {
+	let low = 0;
+	let high = terms.length;
+	while (low < high) {
+		const mid = (low + high) >>> 1;
+		if (terms[mid].term < needle) {
+			low = mid + 1;
+		} else {
+			high = mid;
+		}
+	}
+	return low;
+}
+
+/** Entries with a term starting with `query`, username and alias matches first */
+export function searchIndex(index: SearchIndex, query: string, limit = 50): Array<IndexEntry> {
+	const needle = query.trim().toLowerCase().replace(/^@/, "");
+	if (needle === "") {
+		return [];
+	}
+
+	const byName = new Set<number>();
+	const byOther = new Set<number>();
+	for (let i = lowerBound(index.terms, needle); i < index.terms.length; i++) {
+		const { term, entry, byName: isName } = index.terms[i];
+		if (!term.startsWith(needle)) {
+			break;
+		}
+		(isName ? byName : byOther).add(entry);
+	}
+	const others = [...byOther].filter((entry) => !byName.has(entry));
+	const inOrder = (matched: Array<number>): Array<number> => matched.sort((a, b) => a - b);
+	return [...inOrder([...byName]), ...inOrder(others)]
+		.slice(0, limit)
+		.map((entry) => index.entries[entry]);
+}
@@ -0,0 +1,24 @@
+/** `manifest.json` of the artists dir, written by the encoder */
+export type Manifest = {
+	version: number;
+	/** Of the whole DB */
+	hash: string;
+	/** Hash of every artist and alias file, by username or alias */
+	files: Record<string, string>;
+};
+
+let manifest: Promise<Manifest | undefined> | undefined;
+
+/** Fetched once per page load, bypassing the cache. Undefined if unavailable */
+export function fetchManifest(): Promise<Manifest | undefined> {
+	manifest ??= fetch("/artists/manifest.json", { cache: "no-cache" })
+		.then((res) => (res.ok ? (res.json() as Promise<Manifest>) : undefined))
+		.catch(() => undefined);
+	return manifest;
+}
+
+/** The URL of an artist or alias file, versioned by its hash so a cached stale copy is never used */
+export async function artistFileUrl(name: string): Promise<string> {
+	const hash = (await fetchManifest())?.files[name];
+	return hash === undefined ? `/artists/${name}` : `/artists/${name}?v=${hash}`;
+}
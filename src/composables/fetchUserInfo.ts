@@ -1,6 +1,6 @@
 export async function fetchUserInfo(username: string): Promise<string> {
     try {
-        const res = await fetch(`/artists/${username}`);
+        const res = await fetch(await artistFileUrl(username));
         if (!res.ok) {
             return new Promise((_, reject) => reject("error"));
         }
@@ -9,7 +9,7 @@ export async function fetchUserInfo(username: string): Promise<string> {
             return new Promise((_, reject) => reject("error"));
         }
         if (content.startsWith("@")) {
-            const res = await fetch(`/artists/${content.slice(1)}`);
+            const res = await fetch(await artistFileUrl(content.slice(1)));
             return await res.text();
         }
         return new Promise((resolve) => resolve(content));